@@ -0,0 +1,391 @@
+use crate::util::WEB_CLIENT;
+use lazy_static::lazy_static;
+use reqwest::header::{self, HeaderMap};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Responses are kept this long at most, so that stale ones can be revalidated with their ETag.
+const RESPONSE_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+/// Bytes of the response bodies kept at most.
+const RESPONSE_CAPACITY: usize = 64 * 1024 * 1024;
+
+lazy_static! {
+    static ref STATISTICS: Mutex<BTreeMap<&'static str, Statistics>> = Mutex::new(BTreeMap::new());
+    static ref RESPONSES: Cache<String, Arc<Response>> = Cache::with_weight(
+        "responses",
+        RESPONSE_CAPACITY,
+        RESPONSE_RETENTION,
+        |response| { response.body.len() }
+    );
+}
+
+/// An error either in fetching a resource or in parsing it.
+pub type FetchError = Box<dyn std::error::Error + Send + Sync>;
+pub type FetchResult<T> = Result<T, FetchError>;
+
+#[derive(Clone, Copy, Default)]
+pub struct Statistics {
+    pub hits: usize,
+    pub misses: usize,
+    /// Stale entries checked with the origin, which are neither hits nor misses.
+    pub revalidations: usize,
+}
+
+enum Outcome {
+    Hit,
+    Miss,
+    Revalidation,
+}
+
+/// Lists the hits, misses and revalidations of every cache by its name.
+pub fn statistics() -> Vec<(&'static str, Statistics)> {
+    STATISTICS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, statistics)| (*name, *statistics))
+        .collect()
+}
+
+fn record(name: &'static str, outcome: Outcome) {
+    let mut statistics = STATISTICS.lock().unwrap();
+    let statistics = statistics.entry(name).or_default();
+    match outcome {
+        Outcome::Hit => statistics.hits += 1,
+        Outcome::Miss => statistics.misses += 1,
+        Outcome::Revalidation => statistics.revalidations += 1,
+    }
+}
+
+/// An in-memory cache holding entries whose weights add up to at most `capacity`,
+/// each for `ttl` after it is inserted. Every entry weighs 1 unless weighed otherwise.
+/// The least recently used entries are evicted to make room for a new one.
+pub struct Cache<K, V> {
+    name: &'static str,
+    capacity: usize,
+    ttl: Duration,
+    weigh: fn(&V) -> usize,
+    entries: Mutex<Entries<K, V>>,
+}
+
+struct Entries<K, V> {
+    map: HashMap<K, Entry<V>>,
+    /// Keys by when they have been used, least recently used first.
+    order: BTreeMap<u64, K>,
+    /// Weight of every entry together.
+    weight: usize,
+    /// Counts the uses, which orders them without ties unlike `Instant`.
+    clock: u64,
+}
+
+struct Entry<V> {
+    value: V,
+    weight: usize,
+    inserted_at: Instant,
+    used_at: u64,
+}
+
+impl<K, V> Entries<K, V>
+where
+    K: Eq + Hash,
+{
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn remove<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if let Some(entry) = self.map.remove(key) {
+            self.order.remove(&entry.used_at);
+            self.weight -= entry.weight;
+        }
+    }
+
+    fn remove_least_recent(&mut self) -> bool {
+        let used_at = match self.order.keys().next() {
+            Some(used_at) => *used_at,
+            None => return false,
+        };
+        if let Some(key) = self.order.remove(&used_at) {
+            if let Some(entry) = self.map.remove(&key) {
+                self.weight -= entry.weight;
+            }
+        }
+        true
+    }
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: Clone,
+{
+    pub fn new(name: &'static str, capacity: usize, ttl: Duration) -> Self {
+        Cache::with_weight(name, capacity, ttl, |_| 1)
+    }
+
+    /// Makes a cache whose `capacity` is in the units of `weigh`, e.g. bytes.
+    pub fn with_weight(
+        name: &'static str,
+        capacity: usize,
+        ttl: Duration,
+        weigh: fn(&V) -> usize,
+    ) -> Self {
+        Cache {
+            name,
+            capacity,
+            ttl,
+            weigh,
+            entries: Mutex::new(Entries {
+                map: HashMap::new(),
+                order: BTreeMap::new(),
+                weight: 0,
+                clock: 0,
+            }),
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let value = self.lookup(key);
+        record(
+            self.name,
+            if value.is_some() {
+                Outcome::Hit
+            } else {
+                Outcome::Miss
+            },
+        );
+        value
+    }
+
+    /// Gets an entry without recording whether it is a hit.
+    fn lookup<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let mut entries = self.entries.lock().unwrap();
+        let used_at = entries.tick();
+        let Entries { map, order, .. } = &mut *entries;
+        match map.get_mut(key) {
+            Some(entry) if entry.inserted_at.elapsed() < self.ttl => {
+                if let Some(key) = order.remove(&entry.used_at) {
+                    order.insert(used_at, key);
+                }
+                entry.used_at = used_at;
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        let weight = (self.weigh)(&value);
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(&key);
+        // An entry too heavy for the whole cache would only evict everything else
+        if weight > self.capacity {
+            return;
+        }
+        while entries.weight + weight > self.capacity {
+            if !entries.remove_least_recent() {
+                break;
+            }
+        }
+        let used_at = entries.tick();
+        entries.order.insert(used_at, key.clone());
+        entries.map.insert(
+            key,
+            Entry {
+                value,
+                weight,
+                inserted_at: Instant::now(),
+                used_at,
+            },
+        );
+        entries.weight += weight;
+    }
+}
+
+/// A response body kept for reuse.
+pub struct Response {
+    status: StatusCode,
+    location: Option<String>,
+    etag: Option<String>,
+    body: Vec<u8>,
+    fresh_until: Instant,
+}
+
+impl Response {
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The `Location` header of a redirection.
+    pub fn location(&self) -> Option<&str> {
+        self.location.as_deref()
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.body)
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Fetches `url` with `WEB_CLIENT`, reusing the previous response while it is fresh
+/// by its `Cache-Control`, and revalidating it with its `ETag` once stale.
+pub async fn get(url: &str) -> reqwest::Result<Arc<Response>> {
+    let cached = RESPONSES.lookup(url);
+    match &cached {
+        Some(cached) if cached.fresh_until > Instant::now() => {
+            record(RESPONSES.name, Outcome::Hit);
+            return Ok(cached.clone());
+        }
+        Some(_) => record(RESPONSES.name, Outcome::Revalidation),
+        None => record(RESPONSES.name, Outcome::Miss),
+    }
+
+    let mut request = WEB_CLIENT.get(url);
+    if let Some(etag) = cached.as_ref().and_then(|cached| cached.etag.as_ref()) {
+        request = request.header(header::IF_NONE_MATCH, etag.as_str());
+    }
+    let response = request.send().await?;
+    let max_age = max_age(response.headers());
+    let fresh_until = Instant::now() + max_age.unwrap_or_default();
+
+    if let (StatusCode::NOT_MODIFIED, Some(cached)) = (response.status(), &cached) {
+        let revalidated = Arc::new(Response {
+            status: cached.status,
+            location: cached.location.clone(),
+            etag: cached.etag.clone(),
+            body: cached.body.clone(),
+            fresh_until,
+        });
+        RESPONSES.insert(url.to_string(), revalidated.clone());
+        return Ok(revalidated);
+    }
+
+    let status = response.status();
+    let location = header_text(response.headers(), header::LOCATION);
+    let etag = header_text(response.headers(), header::ETAG);
+    let body = response.bytes().await?.to_vec();
+    let response = Arc::new(Response {
+        status,
+        location,
+        etag,
+        body,
+        fresh_until,
+    });
+    // Keep only what can be reused or revalidated
+    if !status.is_server_error()
+        && max_age.is_some()
+        && (response.etag.is_some() || max_age > Some(Duration::from_secs(0)))
+    {
+        RESPONSES.insert(url.to_string(), response.clone());
+    }
+    Ok(response)
+}
+
+/// How long a response stays fresh by its `Cache-Control`, or `None` if it must not be stored.
+fn max_age(headers: &HeaderMap) -> Option<Duration> {
+    let cache_control = match header_text(headers, header::CACHE_CONTROL) {
+        Some(cache_control) => cache_control,
+        None => return Some(Duration::from_secs(0)),
+    };
+    let mut max_age = Duration::from_secs(0);
+    for directive in cache_control.split(',').map(str::trim) {
+        let directive = directive.to_lowercase();
+        if directive == "no-store" {
+            return None;
+        } else if directive == "no-cache" {
+            return Some(Duration::from_secs(0));
+        } else if let Some(seconds) = directive.strip_prefix("max-age=") {
+            if let Ok(seconds) = seconds.trim_matches('"').parse() {
+                max_age = Duration::from_secs(seconds);
+            }
+        }
+    }
+    Some(max_age)
+}
+
+fn header_text(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Logs the hits, misses and revalidations of every cache periodically.
+pub async fn report_statistics() {
+    const INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+    loop {
+        tokio::time::delay_for(INTERVAL).await;
+        for (name, statistics) in statistics() {
+            log::info!(
+                "Cache {{ Name = {}, Hits = {}, Misses = {}, Revalidations = {} }}",
+                name,
+                statistics.hits,
+                statistics.misses,
+                statistics.revalidations
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evict_least_recently_used_by_weight() {
+        let cache: Cache<&str, String> =
+            Cache::with_weight("test", 10, Duration::from_secs(60), String::len);
+        cache.insert("a", "aaaa".to_string());
+        cache.insert("b", "bbbb".to_string());
+        assert!(cache.get("a").is_some());
+        // `b` is the least recently used, and evicting it is enough
+        cache.insert("c", "cccc".to_string());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+        // Replacing an entry does not count its previous weight
+        cache.insert("c", "cc".to_string());
+        cache.insert("d", "dddd".to_string());
+        assert!(cache.get("a").is_some());
+        // Too heavy for the whole cache
+        cache.insert("e", "e".repeat(11));
+        assert!(cache.get("e").is_none());
+        assert_eq!(cache.entries.lock().unwrap().weight, 10);
+    }
+
+    #[test]
+    fn expire_entries_after_ttl() {
+        let cache: Cache<&str, u32> = Cache::new("test", 10, Duration::from_secs(0));
+        cache.insert("a", 1);
+        assert!(cache.get("a").is_none());
+        let entries = cache.entries.lock().unwrap();
+        assert!(entries.map.is_empty() && entries.order.is_empty());
+        assert_eq!(entries.weight, 0);
+    }
+}
@@ -0,0 +1,41 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[derive(Deserialize)]
+pub struct Config {
+    /// Where callback sessions, documents, subscriptions and chat settings are stored.
+    /// They are kept in memory only and lost on restart if absent.
+    pub storage: Option<PathBuf>,
+    /// A clone of the RustSec advisory database.
+    pub advisory_db: Option<PathBuf>,
+    /// A snapshot of the rustc error code explanations.
+    pub error_index: Option<PathBuf>,
+    #[serde(default)]
+    pub sessions: SessionConfig,
+}
+
+/// Limits on the messages whose buttons are remembered.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// How many messages are remembered at most.
+    pub capacity: usize,
+    /// Seconds after which a message is forgotten if its buttons are not used.
+    pub max_age: u64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            capacity: 10_000,
+            max_age: 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &str) -> Config {
+        let content = std::fs::read_to_string(path).expect("Failed to read the configuration");
+        toml::from_str(&content).expect("Failed to parse the configuration")
+    }
+}
@@ -1,8 +1,12 @@
 use teloxide::prelude::*;
-use teloxide::types::CallbackQuery;
+use teloxide::types::{CallbackQuery, InlineQuery};
 use teloxide::utils::command::BotCommand;
 
+mod cache;
+mod config;
 mod rust;
+mod settings;
+mod storage;
 pub mod util;
 
 fn main() {
@@ -20,6 +24,24 @@ async fn run() {
         .await
         .expect("Failed to get the bot information.");
     let username = information.user.username.unwrap();
+    let config = config::Config::load("config/ketera.toml");
+    if let Some(path) = &config.storage {
+        let storage = storage::SledStorage::open(path).expect("Failed to open the storage");
+        storage::set_storage(std::sync::Arc::new(storage));
+    }
+    // Subscriptions used to be saved to their own file
+    let subscriptions =
+        std::env::var("SUBSCRIPTIONS").unwrap_or_else(|_| "data/subscriptions.json".into());
+    rust::import_subscriptions(std::path::Path::new(&subscriptions)).await;
+    if let Some(path) = &config.advisory_db {
+        rust::load_advisories(path).await;
+    }
+    if let Some(path) = &config.error_index {
+        rust::load_error_index(path).await;
+    }
+    tokio::spawn(rust::watch_releases(bot.clone()));
+    tokio::spawn(rust::sweep_sessions(config.sessions));
+    tokio::spawn(cache::report_statistics());
     Dispatcher::new(bot)
         .messages_handler(move |rx: DispatcherHandlerRx<Message>| {
             rx.commands(username)
@@ -28,6 +50,11 @@ async fn run() {
         .callback_queries_handler(|rx: DispatcherHandlerRx<CallbackQuery>| {
             rx.for_each_concurrent(None, callback_handler)
         })
+        .inline_queries_handler(|rx: DispatcherHandlerRx<InlineQuery>| {
+            rx.for_each_concurrent(None, |cx| async move {
+                rust::inline_query(cx).await.log_on_error().await;
+            })
+        })
         .dispatch()
         .await;
 }
@@ -49,6 +76,93 @@ async fn command_handler(
         Command::Docs => {
             rust::search_crate(cx, args).await.log_on_error().await;
         }
+        Command::Search => {
+            rust::find_crate(cx, args).await.log_on_error().await;
+        }
+        Command::Versions => {
+            rust::list_versions(cx, args).await.log_on_error().await;
+        }
+        Command::Deps => {
+            rust::list_dependencies(cx, args).await.log_on_error().await;
+        }
+        Command::Rdeps => {
+            rust::list_reverse_dependencies(cx, args)
+                .await
+                .log_on_error()
+                .await;
+        }
+        Command::Features => {
+            rust::list_features(cx, args).await.log_on_error().await;
+        }
+        Command::Tree => {
+            rust::dependency_tree(cx, args).await.log_on_error().await;
+        }
+        Command::Compare => {
+            rust::compare_crates(cx, args).await.log_on_error().await;
+        }
+        Command::Advisory => {
+            rust::show_advisories(cx, args).await.log_on_error().await;
+        }
+        Command::Book => {
+            rust::search_book(cx, args, rust::Book::RustProgrammingLanguage)
+                .await
+                .log_on_error()
+                .await;
+        }
+        Command::Reference => {
+            rust::search_book(cx, args, rust::Book::Reference)
+                .await
+                .log_on_error()
+                .await;
+        }
+        Command::Rbe => {
+            rust::search_book(cx, args, rust::Book::RustByExample)
+                .await
+                .log_on_error()
+                .await;
+        }
+        Command::Nomicon => {
+            rust::search_book(cx, args, rust::Book::Nomicon)
+                .await
+                .log_on_error()
+                .await;
+        }
+        Command::Cargobook => {
+            rust::search_book(cx, args, rust::Book::Cargo)
+                .await
+                .log_on_error()
+                .await;
+        }
+        Command::Rust => {
+            rust::rust_releases(cx, args).await.log_on_error().await;
+        }
+        Command::Feature => {
+            rust::show_feature(cx, args).await.log_on_error().await;
+        }
+        Command::Lint => {
+            rust::lookup_lint(cx, args).await.log_on_error().await;
+        }
+        Command::Explain => {
+            rust::explain_error(cx, args).await.log_on_error().await;
+        }
+        Command::Watch => {
+            rust::watch_crate(cx, args).await.log_on_error().await;
+        }
+        Command::Unwatch => {
+            rust::unwatch_crate(cx, args).await.log_on_error().await;
+        }
+        Command::Watching => {
+            rust::list_subscriptions(cx).await.log_on_error().await;
+        }
+        Command::Settings => {
+            rust::chat_settings(cx, args).await.log_on_error().await;
+        }
+        Command::Downloads => {
+            rust::download_statistics(cx, args)
+                .await
+                .log_on_error()
+                .await;
+        }
     };
 }
 
@@ -59,20 +173,84 @@ async fn callback_handler(query: DispatcherHandlerCx<CallbackQuery>) {
         ..
     } = &query.update
     {
-        let session = {
-            let lock = util::CALLBACK_SESSIONS.read().await;
-            lock.get(&(message.chat_id(), message.id)).cloned()
+        let key = (message.chat_id(), message.id);
+        let session = match util::CALLBACK_SESSIONS.get(&key) {
+            Ok(session) => session,
+            Err(e) => {
+                log::error!(
+                    "Failed to load callback session of message {key:?}: {error}",
+                    key = key,
+                    error = e
+                );
+                return;
+            }
         };
         if let Some(session) = session {
             use util::CallbackSession;
             match session {
-                CallbackSession::Docs => {
-                    rust::search_crate_callback(query)
+                CallbackSession::Docs(source) => {
+                    rust::search_crate_callback(query, source)
+                        .await
+                        .log_on_error()
+                        .await;
+                }
+                CallbackSession::CrateList(names) => {
+                    rust::crate_list_callback(query, names)
+                        .await
+                        .log_on_error()
+                        .await;
+                }
+                CallbackSession::Crate { name, version } => {
+                    rust::crate_information_callback(query, name, version)
+                        .await
+                        .log_on_error()
+                        .await;
+                }
+                CallbackSession::Versions(crate_name) => {
+                    rust::list_versions_callback(query, crate_name)
+                        .await
+                        .log_on_error()
+                        .await;
+                }
+                CallbackSession::ReverseDependencies(crate_name) => {
+                    rust::list_reverse_dependencies_callback(query, crate_name)
+                        .await
+                        .log_on_error()
+                        .await;
+                }
+                CallbackSession::Features { name, version } => {
+                    rust::list_features_callback(query, name, version)
+                        .await
+                        .log_on_error()
+                        .await;
+                }
+                CallbackSession::Advisories(ids) => {
+                    rust::advisory_list_callback(query, ids)
+                        .await
+                        .log_on_error()
+                        .await;
+                }
+                CallbackSession::DocsSuggestions(paths) => {
+                    rust::docs_suggestion_callback(query, paths)
+                        .await
+                        .log_on_error()
+                        .await;
+                }
+                CallbackSession::LintSuggestions(names) => {
+                    rust::lint_suggestion_callback(query, names)
+                        .await
+                        .log_on_error()
+                        .await;
+                }
+                CallbackSession::BookChapters(urls) => {
+                    rust::book_chapter_callback(query, urls)
                         .await
                         .log_on_error()
                         .await;
                 }
             }
+        } else {
+            rust::expired_callback(query).await.log_on_error().await;
         }
     }
 }
@@ -86,4 +264,48 @@ enum Command {
     Crate,
     #[command(description = "show the documentation of a crate item")]
     Docs,
+    #[command(description = "search crates on crates.io")]
+    Search,
+    #[command(description = "show the release history of a crate")]
+    Versions,
+    #[command(description = "list the dependencies of a crate")]
+    Deps,
+    #[command(description = "list the crates depending on a crate")]
+    Rdeps,
+    #[command(description = "explore the feature flags of a crate")]
+    Features,
+    #[command(description = "show the resolved dependency tree of a crate")]
+    Tree,
+    #[command(description = "compare crates side by side")]
+    Compare,
+    #[command(description = "show the download statistics of a crate as a chart")]
+    Downloads,
+    #[command(description = "show the security advisories of a crate")]
+    Advisory,
+    #[command(description = "explain a rustc error code")]
+    Explain,
+    #[command(description = "show the documentation of a clippy or rustc lint")]
+    Lint,
+    #[command(description = "show the Rust release channels or the highlights of a release")]
+    Rust,
+    #[command(description = "show an unstable feature and its tracking issue")]
+    Feature,
+    #[command(description = "search The Rust Programming Language")]
+    Book,
+    #[command(description = "search The Rust Reference")]
+    Reference,
+    #[command(description = "search Rust by Example")]
+    Rbe,
+    #[command(description = "search The Rustonomicon")]
+    Nomicon,
+    #[command(description = "search The Cargo Book")]
+    Cargobook,
+    #[command(description = "get notified of the releases of a crate")]
+    Watch,
+    #[command(description = "stop getting notified of the releases of a crate")]
+    Unwatch,
+    #[command(description = "list the crates watched in this chat")]
+    Watching,
+    #[command(description = "show or change the settings of this chat")]
+    Settings,
 }
@@ -0,0 +1,205 @@
+use lazy_static::lazy_static;
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+lazy_static! {
+    static ref DATABASE: RwLock<Database> = RwLock::new(Database::default());
+}
+
+#[derive(Default)]
+struct Database {
+    by_id: HashMap<String, Arc<Advisory>>,
+    /// Advisories of each crate, newest first.
+    by_crate: HashMap<String, Vec<Arc<Advisory>>>,
+}
+
+/// The TOML front matter of an advisory.
+#[derive(Deserialize)]
+struct AdvisoryFile {
+    advisory: AdvisoryMetadata,
+    #[serde(default)]
+    versions: AdvisoryVersions,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryMetadata {
+    id: String,
+    package: String,
+    date: String,
+    url: Option<String>,
+    /// Only present in advisories without a Markdown body.
+    #[serde(default)]
+    title: String,
+    /// Only present in advisories without a Markdown body.
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    categories: Vec<String>,
+    informational: Option<String>,
+    withdrawn: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct AdvisoryVersions {
+    #[serde(default)]
+    patched: Vec<String>,
+    #[serde(default)]
+    unaffected: Vec<String>,
+}
+
+pub struct Advisory {
+    /// e.g. RUSTSEC-2020-0001
+    pub id: String,
+    /// Name of the affected crate.
+    pub package: String,
+    pub title: String,
+    /// Description in Markdown.
+    pub description: String,
+    /// Date of the report, e.g. 2020-01-01
+    pub date: String,
+    pub url: Option<String>,
+    /// Other identifiers of the advisory, e.g. CVE-2020-0001
+    pub aliases: Vec<String>,
+    pub categories: Vec<String>,
+    /// Kind of an informational advisory, e.g. unmaintained or unsound
+    pub informational: Option<String>,
+    /// Date of the withdrawal if the advisory has been withdrawn.
+    pub withdrawn: Option<String>,
+    /// Requirements of the patched versions.
+    pub patched: Vec<String>,
+    /// Requirements of the versions never affected.
+    pub unaffected: Vec<String>,
+}
+
+impl Advisory {
+    /// Whether `version` is neither patched nor unaffected.
+    /// Versions which are not valid semver are considered unaffected.
+    pub fn affects(&self, version: &str) -> bool {
+        let version = match Version::parse(version) {
+            Ok(version) => version,
+            Err(_) => return false,
+        };
+        !self
+            .patched
+            .iter()
+            .chain(self.unaffected.iter())
+            .filter_map(|req| VersionReq::parse(req).ok())
+            .any(|req| req.matches(&version))
+    }
+}
+
+/// Indexes every advisory under `crates` and `rust` of a clone of the RustSec advisory database,
+/// replacing the advisories loaded before. Returns the number of advisories loaded.
+pub async fn load(root: &Path) -> std::io::Result<usize> {
+    let mut database = Database::default();
+    for section in &["crates", "rust"] {
+        let section = root.join(section);
+        if !section.is_dir() {
+            continue;
+        }
+        for package in std::fs::read_dir(section)? {
+            let package = package?.path();
+            if !package.is_dir() {
+                continue;
+            }
+            for file in std::fs::read_dir(package)? {
+                let path = file?.path();
+                let advisory = match path.extension().and_then(|e| e.to_str()) {
+                    Some("md") | Some("toml") => parse(&std::fs::read_to_string(&path)?),
+                    _ => continue,
+                };
+                match advisory {
+                    Some(advisory) => {
+                        let advisory = Arc::new(advisory);
+                        database
+                            .by_crate
+                            .entry(advisory.package.clone())
+                            .or_default()
+                            .push(advisory.clone());
+                        database.by_id.insert(advisory.id.clone(), advisory);
+                    }
+                    None => log::warn!("Failed to parse advisory `{}`", path.display()),
+                }
+            }
+        }
+    }
+    for advisories in database.by_crate.values_mut() {
+        advisories.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| b.id.cmp(&a.id)));
+    }
+    let count = database.by_id.len();
+    *DATABASE.write().await = database;
+    Ok(count)
+}
+
+/// Parses an advisory either in Markdown with TOML front matter, or in plain TOML.
+fn parse(content: &str) -> Option<Advisory> {
+    let (front_matter, body) = match content.trim_start().strip_prefix("```toml") {
+        Some(rest) => {
+            let end = rest.find("\n```")?;
+            (&rest[..end], Some(&rest[end + 4..]))
+        }
+        None => (content, None),
+    };
+    let AdvisoryFile { advisory, versions } = toml::from_str(front_matter).ok()?;
+    let (title, description) = match body {
+        Some(body) => {
+            let body = body.trim_start();
+            match body.strip_prefix("# ") {
+                Some(rest) => {
+                    let end = rest.find('\n').unwrap_or(rest.len());
+                    (
+                        rest[..end].trim().to_string(),
+                        rest[end..].trim().to_string(),
+                    )
+                }
+                None => (advisory.title, body.trim().to_string()),
+            }
+        }
+        None => (advisory.title, advisory.description),
+    };
+    Some(Advisory {
+        id: advisory.id,
+        package: advisory.package,
+        title,
+        description,
+        date: advisory.date,
+        url: advisory.url,
+        aliases: advisory.aliases,
+        categories: advisory.categories,
+        informational: advisory.informational,
+        withdrawn: advisory.withdrawn,
+        patched: versions.patched,
+        unaffected: versions.unaffected,
+    })
+}
+
+pub async fn get_advisory(id: &str) -> Option<Arc<Advisory>> {
+    DATABASE.read().await.by_id.get(&id.to_uppercase()).cloned()
+}
+
+/// Lists the advisories of a crate, newest first.
+pub async fn get_advisories(crate_name: &str) -> Vec<Arc<Advisory>> {
+    DATABASE
+        .read()
+        .await
+        .by_crate
+        .get(crate_name)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Lists the advisories affecting a version of a crate, newest first.
+/// Withdrawn advisories are excluded.
+pub async fn get_affecting(crate_name: &str, version: &str) -> Vec<Arc<Advisory>> {
+    get_advisories(crate_name)
+        .await
+        .into_iter()
+        .filter(|advisory| advisory.withdrawn.is_none() && advisory.affects(version))
+        .collect()
+}
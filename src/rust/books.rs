@@ -0,0 +1,360 @@
+use super::markdown::join_blocks;
+use super::search::{node_text, parse_document_paragraph, Article, CrateDocument};
+use crate::cache::{self, Cache, FetchResult};
+use crate::util::{escape_html_entities, BUTTON_LIMIT};
+use lazy_static::lazy_static;
+use scraper::{ElementRef, Html, Selector};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How many characters of a section are shown around the first match.
+const SNIPPET_LENGTH: usize = 160;
+
+lazy_static! {
+    /// Search indices are revalidated with their ETag once expired, so that they follow new releases.
+    static ref INDEX_CACHE: Cache<Book, Arc<BookIndex>> =
+        Cache::new("book_indices", 5, Duration::from_secs(6 * 60 * 60));
+}
+
+/// The official books built with mdBook.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Book {
+    RustProgrammingLanguage,
+    Reference,
+    RustByExample,
+    Nomicon,
+    Cargo,
+}
+
+impl Book {
+    const ALL: [Book; 5] = [
+        Book::RustProgrammingLanguage,
+        Book::Reference,
+        Book::RustByExample,
+        Book::Nomicon,
+        Book::Cargo,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Book::RustProgrammingLanguage => "The Rust Programming Language",
+            Book::Reference => "The Rust Reference",
+            Book::RustByExample => "Rust by Example",
+            Book::Nomicon => "The Rustonomicon",
+            Book::Cargo => "The Cargo Book",
+        }
+    }
+
+    /// The command searching this book.
+    pub fn command(self) -> &'static str {
+        match self {
+            Book::RustProgrammingLanguage => "book",
+            Book::Reference => "reference",
+            Book::RustByExample => "rbe",
+            Book::Nomicon => "nomicon",
+            Book::Cargo => "cargobook",
+        }
+    }
+
+    fn root(self) -> &'static str {
+        match self {
+            Book::RustProgrammingLanguage => "https://doc.rust-lang.org/book/",
+            Book::Reference => "https://doc.rust-lang.org/reference/",
+            Book::RustByExample => "https://doc.rust-lang.org/rust-by-example/",
+            Book::Nomicon => "https://doc.rust-lang.org/nomicon/",
+            Book::Cargo => "https://doc.rust-lang.org/cargo/",
+        }
+    }
+}
+
+/// A chapter matching a search.
+pub struct SearchResult {
+    /// e.g. https://doc.rust-lang.org/book/ch04-01-what-is-ownership.html
+    pub url: String,
+    /// Headings leading to the best matching section,
+    /// e.g. Understanding Ownership » What Is Ownership? » The Stack and the Heap
+    pub breadcrumbs: String,
+    /// Part of the best matching section around the query, in plain text.
+    pub snippet: String,
+}
+
+/// The sections of a book, as in the document store of its `searchindex.json`.
+struct BookIndex {
+    sections: Vec<Section>,
+}
+
+struct Section {
+    /// Path to the section from the root of the book, e.g. ch04-01-what-is-ownership.html#the-stack-and-the-heap
+    url: String,
+    title: String,
+    body: String,
+    breadcrumbs: String,
+}
+
+#[derive(Deserialize)]
+struct SearchIndexFile {
+    doc_urls: Vec<String>,
+    index: ElasticlunrIndex,
+}
+
+#[derive(Deserialize)]
+struct ElasticlunrIndex {
+    #[serde(rename = "documentStore")]
+    document_store: DocumentStore,
+}
+
+#[derive(Deserialize)]
+struct DocumentStore {
+    docs: HashMap<String, IndexedSection>,
+}
+
+#[derive(Deserialize)]
+struct IndexedSection {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    breadcrumbs: String,
+}
+
+impl BookIndex {
+    /// Finds the chapters whose sections contain every word of `query`, best first.
+    /// Matches in headings weigh more than those in the body.
+    fn search(&self, query: &str, count: usize) -> Vec<SearchResult> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        // The best matching section of each chapter
+        let mut chapters: HashMap<&str, (usize, &Section)> = HashMap::new();
+        for section in &self.sections {
+            let title = section.title.to_lowercase();
+            let breadcrumbs = section.breadcrumbs.to_lowercase();
+            let body = section.body.to_lowercase();
+            let mut score = 0;
+            for term in &terms {
+                let heading_score = if title.contains(term.as_str()) {
+                    10
+                } else if breadcrumbs.contains(term.as_str()) {
+                    5
+                } else {
+                    0
+                };
+                let term_score = heading_score + body.matches(term.as_str()).count().min(5);
+                if term_score == 0 {
+                    score = 0;
+                    break;
+                }
+                score += term_score;
+            }
+            if score == 0 {
+                continue;
+            }
+            let chapter = section.url.split('#').next().unwrap_or_default();
+            let best = chapters.entry(chapter).or_insert((score, section));
+            if best.0 < score {
+                *best = (score, section);
+            }
+        }
+
+        let mut chapters: Vec<(usize, &Section)> = chapters.values().copied().collect();
+        chapters.sort_by(|(a_score, a), (b_score, b)| {
+            b_score.cmp(a_score).then_with(|| a.url.cmp(&b.url))
+        });
+        chapters
+            .into_iter()
+            .take(count)
+            .map(|(_, section)| SearchResult {
+                url: section.url.clone(),
+                breadcrumbs: section.breadcrumbs.clone(),
+                snippet: snippet(&section.body, &terms[0]),
+            })
+            .collect()
+    }
+}
+
+/// Cuts the part of `body` around the first occurrence of `term`.
+fn snippet(body: &str, term: &str) -> String {
+    let chars: Vec<char> = body.chars().collect();
+    let lowercase: Vec<char> = body.to_lowercase().chars().collect();
+    let term: Vec<char> = term.chars().collect();
+    // Lowercasing may change the number of characters, in which case the start is shown
+    let position = if chars.len() == lowercase.len() {
+        lowercase
+            .windows(term.len().max(1))
+            .position(|window| window == term.as_slice())
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    let start = position.saturating_sub(SNIPPET_LENGTH / 4);
+    let end = (start + SNIPPET_LENGTH).min(chars.len());
+    let mut snippet: String = chars[start..end].iter().collect();
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// Searches the chapters of a book.
+pub async fn search(book: Book, query: &str, count: usize) -> FetchResult<Vec<SearchResult>> {
+    let index = get_index(book).await?;
+    let mut results = index.search(query, count);
+    for result in &mut results {
+        result.url = format!("{}{}", book.root(), result.url);
+    }
+    Ok(results)
+}
+
+async fn get_index(book: Book) -> FetchResult<Arc<BookIndex>> {
+    if let Some(index) = INDEX_CACHE.get(&book) {
+        return Ok(index);
+    }
+    let index: SearchIndexFile = {
+        let response = cache::get(&format!("{}searchindex.json", book.root())).await?;
+        if response.status().is_success() {
+            response.json()?
+        } else {
+            // Books built without the JSON index only ship the script assigning it
+            let response = cache::get(&format!("{}searchindex.js", book.root())).await?;
+            let script = response.text();
+            let json = script
+                .trim()
+                .trim_start_matches("Object.assign(window.search, ")
+                .trim_end_matches(';')
+                .trim_end_matches(')');
+            serde_json::from_str(json)?
+        }
+    };
+    let doc_urls = index.doc_urls;
+    let mut sections: Vec<(usize, Section)> = index
+        .index
+        .document_store
+        .docs
+        .into_iter()
+        .filter_map(|(id, section)| {
+            let id: usize = id.parse().ok()?;
+            let url = doc_urls.get(id)?.clone();
+            Some((
+                id,
+                Section {
+                    url,
+                    title: section.title,
+                    body: section.body,
+                    breadcrumbs: section.breadcrumbs,
+                },
+            ))
+        })
+        .collect();
+    sections.sort_by_key(|(id, _)| *id);
+    let index = Arc::new(BookIndex {
+        sections: sections.into_iter().map(|(_, section)| section).collect(),
+    });
+    INDEX_CACHE.insert(book, index.clone());
+    Ok(index)
+}
+
+/// A page of an mdBook in Telegram HTML.
+pub(super) struct Chapter {
+    /// Text of the `h1` heading.
+    pub title: Option<String>,
+    /// Blocks before the first `h2` heading.
+    pub description: Vec<String>,
+    /// Blocks under each `h2` heading.
+    pub sections: Vec<(String, Vec<String>)>,
+}
+
+impl Chapter {
+    pub fn into_sections(self) -> Vec<(String, Article)> {
+        self.sections
+            .into_iter()
+            .map(|(heading, paragraphs)| (heading, Article::Text(join_blocks(&paragraphs))))
+            .collect()
+    }
+}
+
+/// Splits the content of an mdBook page into sections at every `h2` heading.
+pub(super) fn parse_chapter(html: &Html) -> Option<Chapter> {
+    lazy_static! {
+        static ref MAIN_SELECTOR: Selector = Selector::parse("main").unwrap();
+    }
+
+    let main = html.select(&MAIN_SELECTOR).next()?;
+    let mut title = None;
+    let mut description = Vec::new();
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+    for element in main.children().filter_map(ElementRef::wrap) {
+        let block = match element.value().name() {
+            "h1" => {
+                if title.is_none() {
+                    title = Some(node_text(element).trim().to_string());
+                }
+                continue;
+            }
+            "h2" => {
+                sections.push((node_text(element).trim().to_string(), Vec::new()));
+                continue;
+            }
+            "h3" | "h4" | "h5" | "h6" => Some(format!(
+                "<b>{}</b>",
+                escape_html_entities(node_text(element).trim())
+            )),
+            _ => parse_document_paragraph(element),
+        };
+        if let Some(block) = block {
+            match sections.last_mut() {
+                Some((_, paragraphs)) => paragraphs.push(block),
+                None => description.push(block),
+            }
+        }
+    }
+    sections.retain(|(_, paragraphs)| !paragraphs.is_empty());
+    sections.truncate(BUTTON_LIMIT);
+    Some(Chapter {
+        title,
+        description,
+        sections,
+    })
+}
+
+/// Fetches a chapter of a book, with a section per `h2` heading.
+/// Returns `None` if the page is not a chapter of the books.
+pub async fn get_chapter(url: &str) -> FetchResult<Option<CrateDocument>> {
+    let book = match Book::ALL.iter().find(|book| url.starts_with(book.root())) {
+        Some(book) => book,
+        None => return Ok(None),
+    };
+    let page = url.split('#').next().unwrap_or_default();
+    let response = cache::get(page).await?;
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+    let chapter = match parse_chapter(&Html::parse_document(&response.text())) {
+        Some(chapter) => chapter,
+        None => return Ok(None),
+    };
+
+    let title = chapter
+        .title
+        .clone()
+        .unwrap_or_else(|| page[book.root().len()..].to_string());
+    Ok(Some(CrateDocument {
+        title: format!(
+            "📖 <a href=\"{}\">{}</a>",
+            page.replace('"', "&quot;"),
+            escape_html_entities(&title)
+        ),
+        definition: Some(format!("<i>{}</i>", book.title())),
+        portability_note: None,
+        stability_note: None,
+        deprecated: false,
+        description: join_blocks(&chapter.description),
+        sections: chapter.into_sections(),
+    }))
+}
@@ -0,0 +1,92 @@
+use crate::util::size_humanize;
+use chrono::NaiveDate;
+use plotters::prelude::*;
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 480;
+
+/// Renders daily downloads into a PNG chart.
+/// A single series is drawn as a line, several series are stacked on top of each other
+/// in the given order.
+pub fn render_downloads(
+    caption: &str,
+    dates: &[NaiveDate],
+    series: &[(String, Vec<usize>)],
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    use image::ImageEncoder;
+
+    let mut stacked = Vec::with_capacity(series.len());
+    let mut cumulative = vec![0; dates.len()];
+    for (label, downloads) in series {
+        for (sum, count) in cumulative.iter_mut().zip(downloads) {
+            *sum += count;
+        }
+        stacked.push((label, cumulative.clone()));
+    }
+    let max = cumulative.iter().cloned().max().unwrap_or(0).max(1);
+
+    let mut buffer = vec![0; (WIDTH * HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE)?;
+        let mut chart = ChartBuilder::on(&root)
+            .caption(caption, ("sans-serif", 24))
+            .margin(16)
+            .x_label_area_size(32)
+            .y_label_area_size(56)
+            .build_cartesian_2d(0..dates.len().saturating_sub(1), 0..max + max / 10)?;
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .x_labels(6)
+            .x_label_formatter(&|day: &usize| {
+                dates
+                    .get(*day)
+                    .map(|date| date.format("%m-%d").to_string())
+                    .unwrap_or_default()
+            })
+            .y_label_formatter(&|count: &usize| size_humanize(*count))
+            .draw()?;
+
+        if let [(label, downloads)] = series {
+            chart
+                .draw_series(LineSeries::new(
+                    downloads.iter().cloned().enumerate(),
+                    RED.stroke_width(2),
+                ))?
+                .label(label.as_str())
+                .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 16, y)], RED.stroke_width(2)));
+        } else {
+            // Draw the tallest stack first so that each lower one stays visible
+            for (i, (label, downloads)) in stacked.iter().enumerate().rev() {
+                let color = Palette99::pick(i).to_rgba();
+                chart
+                    .draw_series(AreaSeries::new(
+                        downloads.iter().cloned().enumerate(),
+                        0,
+                        color.mix(0.8),
+                    ))?
+                    .label(label.as_str())
+                    .legend(move |(x, y)| {
+                        Rectangle::new([(x, y - 6), (x + 12, y + 6)], color.filled())
+                    });
+            }
+        }
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+        root.present()?;
+    }
+
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png).write_image(
+        &buffer,
+        WIDTH,
+        HEIGHT,
+        image::ColorType::Rgb8,
+    )?;
+    Ok(png)
+}
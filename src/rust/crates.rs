@@ -1,5 +1,15 @@
-use chrono::{DateTime, Utc};
+use crate::cache::{self, Cache, FetchResult};
+use chrono::{DateTime, NaiveDate, Utc};
+use lazy_static::lazy_static;
 use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Duration;
+
+lazy_static! {
+    /// Information of crates by the name and the requested version.
+    static ref INFORMATION_CACHE: Cache<(String, Option<String>), Information> =
+        Cache::new("information", 200, Duration::from_secs(5 * 60));
+}
 
 #[derive(Deserialize)]
 struct CrateResponse {
@@ -30,14 +40,23 @@ struct CrateOwnerResponse {
 }
 
 #[derive(Deserialize)]
-struct CrateVersion {
+pub struct CrateVersion {
+    pub id: u64,
+    /// The name of the crate as published, which may differ in case from the one searched.
+    #[serde(rename = "crate")]
+    pub crate_name: String,
     #[serde(rename = "num")]
-    version: String,
-    crate_size: Option<usize>,
-    license: Option<String>,
+    pub version: String,
+    pub created_at: DateTime<Utc>,
+    pub crate_size: Option<usize>,
+    pub license: Option<String>,
+    pub yanked: bool,
+    pub rust_version: Option<String>,
+    #[serde(default)]
+    pub features: HashMap<String, Vec<String>>,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct CrateUser {
     pub name: Option<String>,
     pub url: String,
@@ -50,8 +69,15 @@ pub struct CrateDependencies {
 
 #[derive(Deserialize)]
 pub struct CrateDependency {
+    #[serde(rename = "crate_id")]
+    pub name: String,
+    pub req: String,
     #[serde(default = "String::default")]
-    kind: String,
+    pub kind: String,
+    pub optional: bool,
+    pub default_features: bool,
+    pub features: Vec<String>,
+    pub target: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -64,6 +90,7 @@ pub struct CrateCategory {
     category: String,
 }
 
+#[derive(Clone)]
 pub struct Information {
     pub name: String,
     pub updated_at: DateTime<Utc>,
@@ -71,6 +98,9 @@ pub struct Information {
     pub downloads: usize,
     pub recent_downloads: usize,
     pub newest_version: String,
+    /// The version this information describes.
+    pub version: String,
+    pub published_at: DateTime<Utc>,
     pub crate_size: usize,
     pub description: String,
     pub homepage: Option<String>,
@@ -84,12 +114,32 @@ pub struct Information {
     pub categories: Vec<String>,
 }
 
-pub async fn get_information(crate_name: &str) -> reqwest::Result<Option<Information>> {
-    use crate::util::WEB_CLIENT;
+/// Fetches the information of a crate.
+/// If `version` is given, it is resolved against the published versions
+/// either as an exact version or as a semver requirement.
+pub async fn get_information(
+    crate_name: &str,
+    version: Option<&str>,
+) -> FetchResult<Option<Information>> {
+    let key = (crate_name.to_string(), version.map(str::to_string));
+    if let Some(information) = INFORMATION_CACHE.get(&key) {
+        return Ok(Some(information));
+    }
+    let information = fetch_information(crate_name, version).await?;
+    if let Some(information) = &information {
+        INFORMATION_CACHE.insert(key, information.clone());
+    }
+    Ok(information)
+}
+
+async fn fetch_information(
+    crate_name: &str,
+    version: Option<&str>,
+) -> FetchResult<Option<Information>> {
     let summary_url = format!("https://crates.io/api/v1/crates/{}", crate_name);
-    let summary_response = WEB_CLIENT.get(&summary_url).send();
+    let summary_response = cache::get(&summary_url);
     let owner_url = format!("https://crates.io/api/v1/crates/{}/owner_user", crate_name);
-    let owner_response = WEB_CLIENT.get(&owner_url).send();
+    let owner_response = cache::get(&owner_url);
 
     let (summary_response, owner_response) = tokio::try_join!(summary_response, owner_response)?;
 
@@ -100,25 +150,27 @@ pub async fn get_information(crate_name: &str) -> reqwest::Result<Option<Informa
     if owner_response.status().is_client_error() {
         return Ok(None);
     }
-    let owner: CrateOwnerResponse = owner_response.json().await?;
+    let owner: CrateOwnerResponse = owner_response.json()?;
 
     let CrateResponse {
         summary,
         versions,
         mut keywords,
         mut categories,
-    } = summary_response.json().await?;
+    } = summary_response.json()?;
 
-    let newest_version = versions
-        .iter()
-        .find(|v| v.version == summary.newest_version);
-    if let Some(newest_version) = newest_version {
+    let target_version = match version {
+        Some(version) => resolve_version(&versions, version),
+        None => versions
+            .iter()
+            .find(|v| v.version == summary.newest_version),
+    };
+    if let Some(target_version) = target_version {
         let dependency_url = format!(
             "https://crates.io/api/v1/crates/{}/{}/dependencies",
-            crate_name, summary.newest_version
+            crate_name, target_version.version
         );
-        let dependency: CrateDependencies =
-            WEB_CLIENT.get(&dependency_url).send().await?.json().await?;
+        let dependency: CrateDependencies = cache::get(&dependency_url).await?.json()?;
         Ok(Some(Information {
             name: summary.name,
             updated_at: summary.updated_at,
@@ -126,7 +178,9 @@ pub async fn get_information(crate_name: &str) -> reqwest::Result<Option<Informa
             downloads: summary.downloads,
             recent_downloads: summary.recent_downloads,
             newest_version: summary.newest_version,
-            crate_size: newest_version.crate_size.unwrap_or(0),
+            version: target_version.version.clone(),
+            published_at: target_version.created_at,
+            crate_size: target_version.crate_size.unwrap_or(0),
             description: summary.description,
             homepage: summary.homepage,
             documentation: summary.documentation,
@@ -138,7 +192,7 @@ pub async fn get_information(crate_name: &str) -> reqwest::Result<Option<Informa
                 .iter()
                 .filter(|d| d.kind == "dev")
                 .count(),
-            license: newest_version.license.clone(),
+            license: target_version.license.clone(),
             keywords: keywords.drain(..).map(|k| k.keyword).collect(),
             categories: categories.drain(..).map(|c| c.category).collect(),
         }))
@@ -146,3 +200,647 @@ pub async fn get_information(crate_name: &str) -> reqwest::Result<Option<Informa
         Ok(None)
     }
 }
+
+/// Finds the version exactly matching `requirement`, or the greatest version
+/// satisfying it as a semver requirement. Yanked versions are only chosen
+/// when they are requested exactly.
+fn resolve_version<'a>(
+    versions: &'a [CrateVersion],
+    requirement: &str,
+) -> Option<&'a CrateVersion> {
+    use semver::{Version, VersionReq};
+    if let Some(exact) = versions.iter().find(|v| v.version == requirement) {
+        return Some(exact);
+    }
+    let requirement = VersionReq::parse(requirement).ok()?;
+    versions
+        .iter()
+        .filter(|v| !v.yanked)
+        .filter_map(|v| Version::parse(&v.version).ok().map(|parsed| (parsed, v)))
+        .filter(|(parsed, _)| requirement.matches(parsed))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, v)| v)
+}
+
+pub async fn get_versions(crate_name: &str) -> FetchResult<Option<Vec<CrateVersion>>> {
+    let summary_url = format!("https://crates.io/api/v1/crates/{}", crate_name);
+    let summary_response = cache::get(&summary_url).await?;
+
+    if summary_response.status().is_client_error() {
+        return Ok(None);
+    }
+
+    let CrateResponse { versions, .. } = summary_response.json()?;
+    Ok(Some(versions))
+}
+
+pub struct Dependencies {
+    /// The version whose dependencies are listed.
+    pub version: String,
+    /// Features declared by the version, used to tell what enables optional dependencies.
+    pub features: HashMap<String, Vec<String>>,
+    pub dependencies: Vec<CrateDependency>,
+}
+
+pub async fn get_dependencies(
+    crate_name: &str,
+    version: Option<&str>,
+) -> FetchResult<Option<Dependencies>> {
+    let summary_url = format!("https://crates.io/api/v1/crates/{}", crate_name);
+    let summary_response = cache::get(&summary_url).await?;
+    if summary_response.status().is_client_error() {
+        return Ok(None);
+    }
+    let CrateResponse {
+        summary,
+        mut versions,
+        ..
+    } = summary_response.json()?;
+    // The same version as `get_information`, so that the crate card and its dependencies agree
+    let target_version = match version {
+        Some(version) => resolve_version(&versions, version),
+        None => versions
+            .iter()
+            .find(|v| v.version == summary.newest_version),
+    }
+    .map(|v| v.version.clone());
+    let target_version = match target_version {
+        Some(target_version) => target_version,
+        None => return Ok(None),
+    };
+    let features = versions
+        .iter_mut()
+        .find(|v| v.version == target_version)
+        .map(|v| std::mem::take(&mut v.features))
+        .unwrap_or_default();
+
+    let dependency_url = format!(
+        "https://crates.io/api/v1/crates/{}/{}/dependencies",
+        crate_name, target_version
+    );
+    let CrateDependencies { dependencies } = cache::get(&dependency_url).await?.json()?;
+    Ok(Some(Dependencies {
+        version: target_version,
+        features,
+        dependencies,
+    }))
+}
+
+impl Dependencies {
+    /// Returns the features of this version that turn on the optional dependency `name`.
+    pub fn enabling_features(&self, name: &str) -> Vec<&str> {
+        let explicit = format!("dep:{}", name);
+        let prefix = format!("{}/", name);
+        let mut enabling: Vec<&str> = self
+            .features
+            .iter()
+            .filter(|(_, enables)| {
+                enables.iter().any(|enabled| {
+                    enabled == name || *enabled == explicit || enabled.starts_with(&prefix)
+                })
+            })
+            .map(|(feature, _)| feature.as_str())
+            .collect();
+        enabling.sort_unstable();
+        enabling
+    }
+
+    /// Returns every feature of this version in name order, with what each of them enables.
+    /// Optional dependencies never referenced by `dep:` get their implicit features.
+    pub fn all_features(&self) -> BTreeMap<String, Vec<String>> {
+        with_implicit_features(
+            &self.features,
+            self.dependencies
+                .iter()
+                .filter(|d| d.optional)
+                .map(|d| d.name.as_str()),
+        )
+    }
+
+    /// Resolves everything `feature` turns on, following other features transitively.
+    pub fn feature_closure(&self, feature: &str) -> FeatureClosure {
+        let optional_dependencies: BTreeSet<&str> = self
+            .dependencies
+            .iter()
+            .filter(|d| d.optional)
+            .map(|d| d.name.as_str())
+            .collect();
+        let mut closure = resolve_features(
+            &self.all_features(),
+            &optional_dependencies,
+            &[feature.to_string()],
+        );
+        closure.features.remove(feature);
+        closure
+    }
+}
+
+/// Adds the implicit features of optional dependencies never referenced by `dep:`.
+pub fn with_implicit_features<'a>(
+    features: &HashMap<String, Vec<String>>,
+    optional_dependencies: impl Iterator<Item = &'a str>,
+) -> BTreeMap<String, Vec<String>> {
+    let mut all_features: BTreeMap<String, Vec<String>> = features
+        .iter()
+        .map(|(feature, enables)| (feature.clone(), enables.clone()))
+        .collect();
+    for dependency in optional_dependencies {
+        let explicit = format!("dep:{}", dependency);
+        let is_explicit = features.values().any(|enables| enables.contains(&explicit));
+        if !is_explicit && !all_features.contains_key(dependency) {
+            all_features.insert(dependency.to_string(), vec![explicit]);
+        }
+    }
+    all_features
+}
+
+/// Resolves everything the `enabled` features turn on, following other features transitively.
+/// `features` must already contain the implicit features.
+pub fn resolve_features(
+    features: &BTreeMap<String, Vec<String>>,
+    optional_dependencies: &BTreeSet<&str>,
+    enabled: &[String],
+) -> FeatureClosure {
+    let mut closure = FeatureClosure::default();
+    let mut stack = enabled.to_vec();
+    while let Some(current) = stack.pop() {
+        if !closure.features.insert(current.clone()) {
+            continue;
+        }
+        let enables = match features.get(&current) {
+            Some(enables) => enables,
+            None => continue,
+        };
+        for enabled in enables {
+            if let Some(dependency) = enabled.strip_prefix("dep:") {
+                closure.dependencies.insert(dependency.to_string());
+            } else if let Some(index) = enabled.find('/') {
+                let (dependency, dependency_feature) = (&enabled[..index], &enabled[index + 1..]);
+                // `foo?/bar` enables `bar` only when `foo` is enabled elsewhere
+                let weak = dependency.ends_with('?');
+                let dependency = dependency.trim_end_matches('?');
+                if !weak && optional_dependencies.contains(dependency) {
+                    if features.contains_key(dependency) {
+                        stack.push(dependency.to_string());
+                    } else {
+                        closure.dependencies.insert(dependency.to_string());
+                    }
+                }
+                closure
+                    .dependency_features
+                    .insert(format!("{}/{}", dependency, dependency_feature));
+            } else {
+                stack.push(enabled.clone());
+            }
+        }
+    }
+    closure
+}
+
+#[derive(Default)]
+pub struct FeatureClosure {
+    /// Features of the crate itself.
+    pub features: BTreeSet<String>,
+    /// Optional dependencies turned on.
+    pub dependencies: BTreeSet<String>,
+    /// Features of dependencies in the form of `dependency/feature`.
+    pub dependency_features: BTreeSet<String>,
+}
+
+#[derive(Deserialize)]
+struct ReverseDependencyResponse {
+    dependencies: Vec<ReverseDependencyEntry>,
+    versions: Vec<ReverseDependencyVersion>,
+    meta: ReverseDependencyMeta,
+}
+
+#[derive(Deserialize)]
+struct ReverseDependencyEntry {
+    version_id: u64,
+    req: String,
+    #[serde(default = "String::default")]
+    kind: String,
+    optional: bool,
+    downloads: usize,
+}
+
+#[derive(Deserialize)]
+struct ReverseDependencyVersion {
+    id: u64,
+    #[serde(rename = "crate")]
+    name: String,
+    num: String,
+}
+
+#[derive(Deserialize)]
+struct ReverseDependencyMeta {
+    total: usize,
+}
+
+pub struct ReverseDependency {
+    /// Name of the dependent crate.
+    pub name: String,
+    /// Version of the dependent crate which has the dependency.
+    pub version: String,
+    pub req: String,
+    pub kind: String,
+    pub optional: bool,
+    pub downloads: usize,
+}
+
+pub struct ReverseDependencies {
+    /// Total number of the dependents across all pages.
+    pub total: usize,
+    pub dependents: Vec<ReverseDependency>,
+}
+
+/// Fetches a page of the crates depending on `crate_name`, sorted by downloads.
+/// `page` starts from 1.
+pub async fn get_reverse_dependencies(
+    crate_name: &str,
+    page: usize,
+    per_page: usize,
+) -> reqwest::Result<Option<ReverseDependencies>> {
+    use crate::util::WEB_CLIENT;
+    let url = format!(
+        "https://crates.io/api/v1/crates/{}/reverse_dependencies",
+        crate_name
+    );
+    let response = WEB_CLIENT
+        .get(&url)
+        .query(&[("page", page), ("per_page", per_page)])
+        .send()
+        .await?;
+
+    if response.status().is_client_error() {
+        return Ok(None);
+    }
+
+    let ReverseDependencyResponse {
+        dependencies,
+        versions,
+        meta,
+    } = response.json().await?;
+    let versions: HashMap<u64, ReverseDependencyVersion> =
+        versions.into_iter().map(|v| (v.id, v)).collect();
+    let dependents = dependencies
+        .into_iter()
+        .filter_map(|dependency| {
+            let version = versions.get(&dependency.version_id)?;
+            Some(ReverseDependency {
+                name: version.name.clone(),
+                version: version.num.clone(),
+                req: dependency.req,
+                kind: dependency.kind,
+                optional: dependency.optional,
+                downloads: dependency.downloads,
+            })
+        })
+        .collect();
+    Ok(Some(ReverseDependencies {
+        total: meta.total,
+        dependents,
+    }))
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    crates: Vec<SearchResult>,
+}
+
+#[derive(Deserialize)]
+pub struct SearchResult {
+    pub name: String,
+    pub description: Option<String>,
+    pub newest_version: String,
+    pub downloads: usize,
+    pub recent_downloads: Option<usize>,
+}
+
+/// Suggests names of crates close to `crate_name` by edit distance, or containing it,
+/// from the search of crates.io. The closest ones come first.
+/// Names are compared case-insensitively, with `-` and `_` considered the same.
+pub async fn suggest_crates(crate_name: &str, count: usize) -> reqwest::Result<Vec<String>> {
+    use crate::util::edit_distance;
+    const SEARCH_COUNT: usize = 20;
+
+    let normalize = |name: &str| name.to_lowercase().replace('-', "_");
+    let query = normalize(crate_name);
+    let max_distance = (query.chars().count() / 3).max(1);
+    let mut suggestions: Vec<(usize, String)> = search_crates(crate_name, SEARCH_COUNT)
+        .await?
+        .into_iter()
+        .filter_map(|result| {
+            let name = normalize(&result.name);
+            // The crate exists, but not the requested version
+            if name == query {
+                return None;
+            }
+            let distance = edit_distance(&query, &name);
+            let contains = query.len() >= 3 && name.contains(&query);
+            if distance <= max_distance || contains {
+                Some((distance, result.name))
+            } else {
+                None
+            }
+        })
+        .collect();
+    // Stable, so that ties keep the order of relevance of crates.io
+    suggestions.sort_by_key(|(distance, _)| *distance);
+    suggestions.truncate(count);
+    Ok(suggestions.into_iter().map(|(_, name)| name).collect())
+}
+
+pub async fn search_crates(query: &str, count: usize) -> reqwest::Result<Vec<SearchResult>> {
+    use crate::util::WEB_CLIENT;
+    let per_page = count.to_string();
+    let response: SearchResponse = WEB_CLIENT
+        .get("https://crates.io/api/v1/crates")
+        .query(&[("q", query), ("per_page", &per_page)])
+        .send()
+        .await?
+        .json()
+        .await?;
+    Ok(response.crates)
+}
+
+#[derive(Deserialize)]
+struct DownloadsResponse {
+    version_downloads: Vec<VersionDownloads>,
+    meta: DownloadsMeta,
+}
+
+#[derive(Deserialize)]
+struct VersionDownloads {
+    version: u64,
+    downloads: usize,
+    date: NaiveDate,
+}
+
+#[derive(Deserialize)]
+struct DownloadsMeta {
+    extra_downloads: Vec<ExtraDownloads>,
+}
+
+#[derive(Deserialize)]
+struct ExtraDownloads {
+    downloads: usize,
+    date: NaiveDate,
+}
+
+pub struct Downloads {
+    /// Consecutive days covered by the series, oldest first.
+    pub dates: Vec<NaiveDate>,
+    /// Daily downloads of the versions listed individually by crates.io.
+    pub versions: Vec<(String, Vec<usize>)>,
+    /// Daily downloads of all the other versions together.
+    pub others: Vec<usize>,
+}
+
+impl Downloads {
+    /// Daily downloads of every version together.
+    pub fn total(&self) -> Vec<usize> {
+        let mut total = self.others.clone();
+        for (_, downloads) in &self.versions {
+            for (sum, count) in total.iter_mut().zip(downloads) {
+                *sum += count;
+            }
+        }
+        total
+    }
+
+    /// Daily downloads grouped by semver-compatible series, e.g. `1.x` or `0.3.x`, oldest first.
+    /// Downloads of the versions not listed individually are grouped as `others`.
+    pub fn by_major(&self) -> Vec<(String, Vec<usize>)> {
+        use semver::Version;
+        let mut groups: BTreeMap<(u64, u64), Vec<usize>> = BTreeMap::new();
+        for (version, downloads) in &self.versions {
+            let key = match Version::parse(version) {
+                Ok(parsed) if parsed.major == 0 => (0, parsed.minor),
+                Ok(parsed) => (parsed.major, 0),
+                Err(_) => continue,
+            };
+            let group = groups
+                .entry(key)
+                .or_insert_with(|| vec![0; self.dates.len()]);
+            for (sum, count) in group.iter_mut().zip(downloads) {
+                *sum += count;
+            }
+        }
+        let mut series: Vec<(String, Vec<usize>)> = groups
+            .into_iter()
+            .map(|((major, minor), downloads)| {
+                let label = if major == 0 {
+                    format!("0.{}.x", minor)
+                } else {
+                    format!("{}.x", major)
+                };
+                (label, downloads)
+            })
+            .collect();
+        if self.others.iter().any(|count| *count > 0) {
+            series.insert(0, ("others".to_string(), self.others.clone()));
+        }
+        series
+    }
+}
+
+/// Fetches the daily downloads of a crate over the last `days` days.
+pub async fn get_downloads(crate_name: &str, days: usize) -> FetchResult<Option<Downloads>> {
+    let summary_url = format!("https://crates.io/api/v1/crates/{}", crate_name);
+    let summary_response = cache::get(&summary_url);
+    let downloads_url = format!("https://crates.io/api/v1/crates/{}/downloads", crate_name);
+    let downloads_response = cache::get(&downloads_url);
+
+    let (summary_response, downloads_response) =
+        tokio::try_join!(summary_response, downloads_response)?;
+
+    if summary_response.status().is_client_error() || downloads_response.status().is_client_error()
+    {
+        return Ok(None);
+    }
+
+    let CrateResponse { versions, .. } = summary_response.json()?;
+    let DownloadsResponse {
+        version_downloads,
+        meta,
+    } = downloads_response.json()?;
+
+    let last = version_downloads
+        .iter()
+        .map(|d| d.date)
+        .chain(meta.extra_downloads.iter().map(|d| d.date))
+        .max()
+        .unwrap_or_else(|| Utc::now().date_naive());
+    let first = last - chrono::Duration::days(days.max(1) as i64 - 1);
+    let mut dates = Vec::with_capacity(days);
+    let mut date = first;
+    while date <= last {
+        dates.push(date);
+        date = match date.succ_opt() {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    let day_index = |date: NaiveDate| {
+        if date < first || date > last {
+            None
+        } else {
+            Some((date - first).num_days() as usize)
+        }
+    };
+
+    let version_names: HashMap<u64, &str> = versions
+        .iter()
+        .map(|v| (v.id, v.version.as_str()))
+        .collect();
+    let mut by_version: HashMap<u64, Vec<usize>> = HashMap::new();
+    for entry in version_downloads {
+        if let Some(index) = day_index(entry.date) {
+            by_version
+                .entry(entry.version)
+                .or_insert_with(|| vec![0; dates.len()])[index] += entry.downloads;
+        }
+    }
+    let mut others = vec![0; dates.len()];
+    for entry in meta.extra_downloads {
+        if let Some(index) = day_index(entry.date) {
+            others[index] += entry.downloads;
+        }
+    }
+    let versions = by_version
+        .into_iter()
+        .filter_map(|(id, downloads)| {
+            version_names
+                .get(&id)
+                .map(|version| (version.to_string(), downloads))
+        })
+        .collect();
+    Ok(Some(Downloads {
+        dates,
+        versions,
+        others,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(versions: &[(&str, bool)]) -> Vec<CrateVersion> {
+        versions
+            .iter()
+            .map(|(version, yanked)| CrateVersion {
+                id: 0,
+                crate_name: "demo".to_string(),
+                version: version.to_string(),
+                created_at: Utc::now(),
+                crate_size: None,
+                license: None,
+                yanked: *yanked,
+                rust_version: None,
+                features: HashMap::new(),
+            })
+            .collect()
+    }
+
+    fn resolve<'a>(versions: &'a [CrateVersion], requirement: &str) -> Option<&'a str> {
+        resolve_version(versions, requirement).map(|v| v.version.as_str())
+    }
+
+    #[test]
+    fn resolve_exact_version() {
+        let versions = versions(&[("1.0.2", true), ("1.0.1", false), ("1.0.0", false)]);
+        assert_eq!(resolve(&versions, "1.0.1"), Some("1.0.1"));
+        // Yanked versions are chosen only when requested exactly
+        assert_eq!(resolve(&versions, "1.0.2"), Some("1.0.2"));
+    }
+
+    #[test]
+    fn resolve_greatest_matching_version() {
+        let versions = versions(&[
+            ("2.0.0", false),
+            ("1.2.0", true),
+            ("1.1.0", false),
+            ("1.0.0", false),
+        ]);
+        assert_eq!(resolve(&versions, "1"), Some("1.1.0"));
+        assert_eq!(resolve(&versions, "^1.0"), Some("1.1.0"));
+        assert_eq!(resolve(&versions, "~1.0"), Some("1.0.0"));
+        assert_eq!(resolve(&versions, "3"), None);
+        assert_eq!(resolve(&versions, "latest"), None);
+    }
+
+    fn optional_dependency(name: &str) -> CrateDependency {
+        CrateDependency {
+            name: name.to_string(),
+            req: "^1".to_string(),
+            kind: "normal".to_string(),
+            optional: true,
+            default_features: true,
+            features: Vec::new(),
+            target: None,
+        }
+    }
+
+    fn dependencies(features: &[(&str, &[&str])]) -> Dependencies {
+        Dependencies {
+            version: "1.0.0".to_string(),
+            features: features
+                .iter()
+                .map(|(feature, enables)| {
+                    let enables = enables.iter().map(|e| e.to_string()).collect();
+                    (feature.to_string(), enables)
+                })
+                .collect(),
+            dependencies: vec![
+                optional_dependency("serde"),
+                optional_dependency("rand"),
+                optional_dependency("log"),
+            ],
+        }
+    }
+
+    fn names(set: &BTreeSet<String>) -> Vec<&str> {
+        set.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn all_features_adds_implicit_features() {
+        let dependencies = dependencies(&[("std", &[]), ("random", &["dep:rand"])]);
+        let features = dependencies.all_features();
+        let names: Vec<&str> = features.keys().map(String::as_str).collect();
+        // `rand` is referenced by `dep:`, so it has no implicit feature
+        assert_eq!(names, vec!["log", "random", "serde", "std"]);
+        assert_eq!(features["serde"], vec!["dep:serde"]);
+    }
+
+    #[test]
+    fn feature_closure_follows_features_transitively() {
+        let dependencies = dependencies(&[
+            ("default", &["std", "derive"]),
+            ("std", &["alloc", "log?/std"]),
+            ("alloc", &[]),
+            ("derive", &["serde/derive", "dep:rand"]),
+        ]);
+        let closure = dependencies.feature_closure("default");
+        // `serde/derive` turns on the implicit feature of `serde`
+        assert_eq!(
+            names(&closure.features),
+            vec!["alloc", "derive", "serde", "std"]
+        );
+        // A weak feature does not turn on its optional dependency
+        assert_eq!(names(&closure.dependencies), vec!["rand", "serde"]);
+        assert_eq!(
+            names(&closure.dependency_features),
+            vec!["log/std", "serde/derive"]
+        );
+    }
+
+    #[test]
+    fn feature_closure_survives_cycles() {
+        let dependencies = dependencies(&[("a", &["b"]), ("b", &["a"])]);
+        let closure = dependencies.feature_closure("a");
+        assert_eq!(names(&closure.features), vec!["b"]);
+    }
+}
@@ -0,0 +1,152 @@
+use super::markdown::{self, join_blocks, Block};
+use super::search::{Article, CrateDocument};
+use crate::util::escape_html_entities;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::sync::RwLock;
+
+lazy_static! {
+    static ref EXPLANATIONS: RwLock<HashMap<String, CrateDocument>> = RwLock::new(HashMap::new());
+}
+
+/// Indexes the explanations of a snapshot of the rustc error index, i.e. the `E0000.md` files
+/// of `compiler/rustc_error_codes/src/error_codes` in the Rust repository,
+/// replacing the explanations loaded before. Returns the number of explanations loaded.
+pub async fn load(root: &Path) -> std::io::Result<usize> {
+    let mut explanations = HashMap::new();
+    for file in std::fs::read_dir(root)? {
+        let path = file?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("md") {
+            continue;
+        }
+        let code = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(code) if code.starts_with('E') => code.to_string(),
+            _ => continue,
+        };
+        let explanation = parse(&code, &std::fs::read_to_string(&path)?);
+        explanations.insert(code, explanation);
+    }
+    let count = explanations.len();
+    *EXPLANATIONS.write().await = explanations;
+    Ok(count)
+}
+
+/// Finds the explanation of an error code, which may omit the `E` and the leading zeros,
+/// e.g. `E0382`, `e0382` or `382`.
+pub async fn get_explanation(code: &str) -> Option<CrateDocument> {
+    let code = normalize_code(code)?;
+    EXPLANATIONS.read().await.get(&code).cloned()
+}
+
+fn normalize_code(code: &str) -> Option<String> {
+    let code = code.trim();
+    let number = code
+        .strip_prefix('E')
+        .or_else(|| code.strip_prefix('e'))
+        .unwrap_or(code);
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("E{:0>4}", number))
+}
+
+/// Parses an explanation in Markdown.
+/// Every code example starts a section, with the paragraphs following it.
+fn parse(code: &str, markdown: &str) -> CrateDocument {
+    let mut description = Vec::new();
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+    let mut after_erroneous = false;
+    for block in markdown::blocks(markdown) {
+        let block = match block {
+            Block::Heading(heading) => format!("<b>{}</b>", escape_html_entities(&heading)),
+            Block::Text(html) => html,
+            Block::Code { info, html } => {
+                let erroneous = info.split(',').any(|attribute| attribute == "compile_fail");
+                let kind = if erroneous {
+                    "Erroneous example"
+                } else if after_erroneous {
+                    "Fixed example"
+                } else {
+                    "Example"
+                };
+                after_erroneous = erroneous;
+                sections.push((format!("{}. {}", sections.len() + 1, kind), Vec::new()));
+                html
+            }
+        };
+        match sections.last_mut() {
+            Some((_, paragraphs)) => paragraphs.push(block),
+            None => description.push(block),
+        }
+    }
+
+    CrateDocument {
+        title: format!(
+            "<a href=\"https://doc.rust-lang.org/error_codes/{code}.html\">{code}</a>",
+            code = code
+        ),
+        definition: None,
+        portability_note: None,
+        stability_note: None,
+        deprecated: false,
+        description: join_blocks(&description),
+        sections: sections
+            .into_iter()
+            .map(|(heading, paragraphs)| (heading, Article::Text(join_blocks(&paragraphs))))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_codes() {
+        assert_eq!(normalize_code("E0382").as_deref(), Some("E0382"));
+        assert_eq!(normalize_code("e0382").as_deref(), Some("E0382"));
+        assert_eq!(normalize_code(" 382 ").as_deref(), Some("E0382"));
+        assert_eq!(normalize_code("E1").as_deref(), Some("E0001"));
+        assert_eq!(normalize_code("E"), None);
+        assert_eq!(normalize_code("E03x2"), None);
+    }
+
+    #[test]
+    fn parse_examples_into_sections() {
+        let markdown = "A value was used after move.\n\
+            \n\
+            Erroneous code example:\n\
+            \n\
+            ```compile_fail,E0382\n\
+            # fn main() {\n\
+            let a = vec![1];\n\
+            # }\n\
+            ```\n\
+            \n\
+            Clone it instead:\n\
+            \n\
+            ```\n\
+            let b = a.clone();\n\
+            ```\n";
+        let document = parse("E0382", markdown);
+        assert!(document.title.contains(">E0382</a>"));
+        assert_eq!(
+            document.description,
+            "A value was used after move.\nErroneous code example:\n"
+        );
+        let headings: Vec<&str> = document
+            .sections
+            .iter()
+            .map(|(heading, _)| heading.as_str())
+            .collect();
+        assert_eq!(headings, vec!["1. Erroneous example", "2. Fixed example"]);
+        match &document.sections[0].1 {
+            Article::Text(text) => assert_eq!(
+                text,
+                "<pre><code class=\"language-rust\">let a = vec![1];</code></pre>\nClone it instead:\n"
+            ),
+            Article::SubDocuments(_) => panic!("an example must be text"),
+        }
+    }
+}
@@ -0,0 +1,529 @@
+use super::markdown::{self, join_blocks, Block};
+use super::releases;
+use super::search::{node_text, parse_document_paragraph, Article, CrateDocument};
+use crate::cache::{self, Cache, FetchResult};
+use crate::util::{edit_distance, escape_html_entities};
+use lazy_static::lazy_static;
+use scraper::{ElementRef, Html, Selector};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+const CLIPPY_LINTS: &str = "https://rust-lang.github.io/rust-clippy/master/lints.json";
+const CLIPPY_LINT_LIST: &str = "https://rust-lang.github.io/rust-clippy/master/index.html";
+const RUSTC_LINT_GROUPS: &str = "https://doc.rust-lang.org/rustc/lints/groups.html";
+/// Pages listing the built-in lints of rustc by their default level,
+/// under the documentation of a release, e.g. `https://doc.rust-lang.org/1.41.0/`.
+const RUSTC_LINT_LISTINGS: [(&str, &str); 3] = [
+    ("allow", "rustc/lints/listing/allowed-by-default.html"),
+    ("warn", "rustc/lints/listing/warn-by-default.html"),
+    ("deny", "rustc/lints/listing/deny-by-default.html"),
+];
+/// The first release whose rustc book lists the lints.
+const FIRST_LISTED_MINOR: i64 = 28;
+
+lazy_static! {
+    static ref LINT_CACHE: Cache<(), Arc<LintIndex>> =
+        Cache::new("lints", 1, Duration::from_secs(24 * 60 * 60));
+    static ref RUSTC_VERSION_CACHE: Cache<String, Option<i64>> = Cache::new(
+        "rustc_lint_versions",
+        100,
+        Duration::from_secs(24 * 60 * 60)
+    );
+    // The listings of a release never change
+    static ref RUSTC_LISTING_CACHE: Cache<i64, Arc<HashSet<String>>> = Cache::new(
+        "rustc_lint_listings",
+        20,
+        Duration::from_secs(7 * 24 * 60 * 60)
+    );
+}
+
+/// The lints of clippy and rustc.
+struct LintIndex {
+    lints: Vec<Lint>,
+}
+
+struct Lint {
+    /// e.g. `clippy`, or `rustc` for the built-in lints
+    tool: &'static str,
+    /// e.g. needless_borrow
+    name: String,
+    document: CrateDocument,
+}
+
+impl Lint {
+    /// The name to refer to the lint with, e.g. clippy::needless_borrow
+    fn full_name(&self) -> String {
+        match self.tool {
+            "rustc" => self.name.clone(),
+            tool => format!("{}::{}", tool, self.name),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ClippyLint {
+    id: String,
+    group: String,
+    level: String,
+    #[serde(default)]
+    docs: String,
+    version: Option<String>,
+}
+
+impl LintIndex {
+    fn find(&self, name: &str) -> Option<&Lint> {
+        let (tool, name) = normalize_name(name);
+        self.lints
+            .iter()
+            .find(|lint| lint.name == name && tool.is_none_or(|tool| tool == lint.tool))
+    }
+
+    /// Lists the names of the lints similar to `name`, most similar first.
+    fn suggest(&self, name: &str, count: usize) -> Vec<String> {
+        let (tool, name) = normalize_name(name);
+        let max_distance = (name.chars().count() / 3).max(1);
+        let mut suggestions: Vec<(usize, String)> = self
+            .lints
+            .iter()
+            .filter(|lint| tool.is_none_or(|tool| tool == lint.tool))
+            .filter_map(|lint| {
+                let distance = edit_distance(&name, &lint.name);
+                let contains = name.len() >= 3 && lint.name.contains(&name);
+                if distance <= max_distance || contains {
+                    Some((distance, lint.full_name()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        suggestions.sort_by(|(a_distance, a_name), (b_distance, b_name)| {
+            a_distance
+                .cmp(b_distance)
+                .then_with(|| a_name.len().cmp(&b_name.len()))
+        });
+        suggestions
+            .into_iter()
+            .map(|(_, name)| name)
+            .take(count)
+            .collect()
+    }
+}
+
+/// Splits a lint name into its tool and its name as the lint lists spell it,
+/// e.g. `clippy::Needless-Borrow` into `clippy` and `needless_borrow`.
+fn normalize_name(name: &str) -> (Option<&'static str>, String) {
+    let name = name.trim().to_lowercase().replace('-', "_");
+    if let Some(name) = name.strip_prefix("clippy::") {
+        (Some("clippy"), name.to_string())
+    } else if let Some(name) = name.strip_prefix("rustc::") {
+        (Some("rustc"), name.to_string())
+    } else {
+        (None, name)
+    }
+}
+
+/// Finds the document of a lint of clippy or rustc by its name,
+/// e.g. `needless_borrow`, `clippy::needless_borrow` or `unused-must-use`.
+pub async fn get_lint(name: &str) -> FetchResult<Option<CrateDocument>> {
+    let index = get_index().await?;
+    let lint = match index.find(name) {
+        Some(lint) => lint,
+        None => return Ok(None),
+    };
+    let mut document = lint.document.clone();
+    // Clippy tells the version of each lint, rustc does not
+    if lint.tool == "rustc" {
+        if let Some(minor) = get_rustc_version(&lint.name.replace('_', "-")).await {
+            let since = if minor <= FIRST_LISTED_MINOR {
+                format!("Since <b>1.{}.0</b> or earlier", minor)
+            } else {
+                format!("Since <b>1.{}.0</b>", minor)
+            };
+            document.definition = Some(match document.definition {
+                Some(definition) => format!("{} · {}", definition, since),
+                None => since,
+            });
+        }
+    }
+    Ok(Some(document))
+}
+
+/// Lists the names of the lints similar to `name`, most similar first.
+pub async fn suggest_lints(name: &str, count: usize) -> FetchResult<Vec<String>> {
+    Ok(get_index().await?.suggest(name, count))
+}
+
+/// Indexes the lints of every source. A source which fails is skipped,
+/// and the index is then fetched again on the next lookup instead of being cached.
+async fn get_index() -> FetchResult<Arc<LintIndex>> {
+    if let Some(index) = LINT_CACHE.get(&()) {
+        return Ok(index);
+    }
+    let mut complete = true;
+    let mut lints = match get_clippy_lints().await {
+        Err(e) => {
+            log::error!("Failed to get clippy lints: {}", e);
+            complete = false;
+            Vec::new()
+        }
+        Ok(lints) => lints,
+    };
+    let (rustc_lints, rustc_complete) = get_rustc_lints().await;
+    lints.extend(rustc_lints);
+    complete &= rustc_complete;
+    if lints.is_empty() {
+        return Err("no lint source responded".into());
+    }
+    let index = Arc::new(LintIndex { lints });
+    if complete {
+        LINT_CACHE.insert((), index.clone());
+    }
+    Ok(index)
+}
+
+async fn get_clippy_lints() -> FetchResult<Vec<Lint>> {
+    let response = cache::get(CLIPPY_LINTS).await?;
+    if !response.status().is_success() {
+        return Err(format!("clippy lints responded with {}", response.status()).into());
+    }
+    let lints: Vec<ClippyLint> = response.json()?;
+    Ok(lints
+        .into_iter()
+        .map(|lint| {
+            let url = format!("{}#{}", CLIPPY_LINT_LIST, lint.id);
+            let document = lint_document(
+                &format!("clippy::{}", lint.id),
+                &url,
+                std::slice::from_ref(&lint.group),
+                &lint.level,
+                lint.version.as_deref(),
+                markdown::blocks(&lint.docs),
+            );
+            Lint {
+                tool: "clippy",
+                name: lint.id,
+                document,
+            }
+        })
+        .collect())
+}
+
+/// Lists the lints of every listing of rustc which responds,
+/// and whether every listing and the groups responded.
+async fn get_rustc_lints() -> (Vec<Lint>, bool) {
+    let mut complete = true;
+    let groups = match get_rustc_lint_groups().await {
+        Err(e) => {
+            log::error!("Failed to get rustc lint groups: {}", e);
+            complete = false;
+            HashMap::new()
+        }
+        Ok(groups) => groups,
+    };
+    let mut lints = Vec::new();
+    for (level, page) in RUSTC_LINT_LISTINGS.iter() {
+        let url = format!("https://doc.rust-lang.org/{}", page);
+        let html = match get_rustc_listing(&url).await {
+            Err(e) => {
+                log::error!("Failed to get rustc lints of `{}`: {}", url, e);
+                complete = false;
+                continue;
+            }
+            Ok(html) => html,
+        };
+        for (anchor, blocks) in parse_rustc_listing(&html) {
+            let name = anchor.replace('-', "_");
+            let document = lint_document(
+                &name,
+                &format!("{}#{}", url, anchor),
+                groups.get(&name).map(Vec::as_slice).unwrap_or_default(),
+                level,
+                None,
+                blocks,
+            );
+            lints.push(Lint {
+                tool: "rustc",
+                name,
+                document,
+            });
+        }
+    }
+    (lints, complete)
+}
+
+async fn get_rustc_listing(url: &str) -> FetchResult<Html> {
+    let response = cache::get(url).await?;
+    if !response.status().is_success() {
+        return Err(format!("rustc lints responded with {}", response.status()).into());
+    }
+    Ok(Html::parse_document(&response.text()))
+}
+
+/// Finds the minor version of the first release whose rustc book lists a lint,
+/// by a binary search over the releases. Returns `None` on failure,
+/// as the version is only an addition to the lint.
+async fn get_rustc_version(anchor: &str) -> Option<i64> {
+    if let Some(minor) = RUSTC_VERSION_CACHE.get(anchor) {
+        return minor;
+    }
+    // The current stable lists the lint, as it is where the lint was found
+    let mut listed = releases::schedule(releases::today()).stable;
+    let mut unlisted = FIRST_LISTED_MINOR - 1;
+    while listed - unlisted > 1 {
+        let minor = unlisted + (listed - unlisted) / 2;
+        match is_rustc_lint_listed(anchor, minor).await {
+            Err(e) => {
+                log::warn!(
+                    "Failed to find the version of rustc lint `{}`: {}",
+                    anchor,
+                    e
+                );
+                return None;
+            }
+            Ok(true) => listed = minor,
+            Ok(false) => unlisted = minor,
+        }
+    }
+    RUSTC_VERSION_CACHE.insert(anchor.to_string(), Some(listed));
+    Some(listed)
+}
+
+async fn is_rustc_lint_listed(anchor: &str, minor: i64) -> FetchResult<bool> {
+    Ok(get_rustc_listed(minor).await?.contains(anchor))
+}
+
+/// Reads the anchors of the lints listed in the rustc book of `1.{minor}.0`.
+async fn get_rustc_listed(minor: i64) -> FetchResult<Arc<HashSet<String>>> {
+    lazy_static! {
+        static ref ANCHOR_SELECTOR: Selector = Selector::parse("[id]").unwrap();
+    }
+
+    if let Some(listed) = RUSTC_LISTING_CACHE.get(&minor) {
+        return Ok(listed);
+    }
+    let mut listed = HashSet::new();
+    for (_, page) in RUSTC_LINT_LISTINGS.iter() {
+        let url = format!("https://doc.rust-lang.org/1.{}.0/{}", minor, page);
+        let response = cache::get(&url).await?;
+        if response.status().is_server_error() {
+            return Err(format!("rustc lints responded with {}", response.status()).into());
+        }
+        if response.status().is_success() {
+            let html = Html::parse_document(&response.text());
+            listed.extend(
+                html.select(&ANCHOR_SELECTOR)
+                    .filter_map(|element| element.value().attr("id"))
+                    .map(str::to_string),
+            );
+        }
+    }
+    let listed = Arc::new(listed);
+    RUSTC_LISTING_CACHE.insert(minor, listed.clone());
+    Ok(listed)
+}
+
+/// Reads the groups each rustc lint belongs to, by the name of the lint.
+async fn get_rustc_lint_groups() -> FetchResult<HashMap<String, Vec<String>>> {
+    lazy_static! {
+        static ref ROW_SELECTOR: Selector = Selector::parse("table tbody tr").unwrap();
+        static ref CELL_SELECTOR: Selector = Selector::parse("td").unwrap();
+    }
+
+    let response = cache::get(RUSTC_LINT_GROUPS).await?;
+    if !response.status().is_success() {
+        return Err(format!("rustc lint groups responded with {}", response.status()).into());
+    }
+    let html = Html::parse_document(&response.text());
+    let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+    for row in html.select(&ROW_SELECTOR) {
+        let cells: Vec<String> = row.select(&CELL_SELECTOR).map(node_text).collect();
+        if let (Some(group), Some(lints)) = (cells.first(), cells.get(2)) {
+            for lint in lints
+                .split(',')
+                .map(str::trim)
+                .filter(|lint| !lint.is_empty())
+            {
+                groups
+                    .entry(lint.replace('-', "_"))
+                    .or_default()
+                    .push(group.trim().to_string());
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/// Splits a listing of rustc lints at every `h2` heading, whose anchor is the name of the lint.
+/// The blocks after each heading are converted into Telegram HTML.
+fn parse_rustc_listing(html: &Html) -> Vec<(String, Vec<Block>)> {
+    lazy_static! {
+        static ref MAIN_SELECTOR: Selector = Selector::parse("main").unwrap();
+    }
+
+    let mut lints: Vec<(String, Vec<Block>)> = Vec::new();
+    let main = match html.select(&MAIN_SELECTOR).next() {
+        Some(main) => main,
+        None => return lints,
+    };
+    for element in main.children().filter_map(ElementRef::wrap) {
+        let name = element.value().name();
+        if name == "h2" {
+            if let Some(anchor) = element.value().id() {
+                lints.push((anchor.to_string(), Vec::new()));
+            }
+            continue;
+        }
+        let blocks = match lints.last_mut() {
+            Some((_, blocks)) => blocks,
+            None => continue,
+        };
+        match name {
+            "h3" | "h4" => blocks.push(Block::Heading(node_text(element).trim().to_string())),
+            "pre" => {
+                if let Some(html) = parse_document_paragraph(element) {
+                    blocks.push(Block::Code {
+                        info: String::new(),
+                        html,
+                    });
+                }
+            }
+            _ => {
+                if let Some(html) = parse_document_paragraph(element) {
+                    blocks.push(Block::Text(html));
+                }
+            }
+        }
+    }
+    lints
+}
+
+/// Builds the document of a lint with a section per heading.
+/// The description is the text before the first heading, or else the first section,
+/// which is usually "What it does" and is then left out of the sections.
+fn lint_document(
+    name: &str,
+    url: &str,
+    groups: &[String],
+    level: &str,
+    version: Option<&str>,
+    blocks: Vec<Block>,
+) -> CrateDocument {
+    let mut description = Vec::new();
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+    for block in blocks {
+        match block {
+            Block::Heading(heading) => sections.push((heading, Vec::new())),
+            Block::Code { html, .. } | Block::Text(html) => match sections.last_mut() {
+                Some((_, paragraphs)) => paragraphs.push(html),
+                None => description.push(html),
+            },
+        }
+    }
+    sections.retain(|(_, paragraphs)| !paragraphs.is_empty());
+    let description = if description.is_empty() && !sections.is_empty() {
+        let (_, paragraphs) = sections.remove(0);
+        join_blocks(&paragraphs)
+    } else {
+        join_blocks(&description)
+    };
+
+    let mut definition = vec![format!("Level <b>{}</b>", escape_html_entities(level))];
+    if !groups.is_empty() {
+        definition.insert(
+            0,
+            format!("Group <b>{}</b>", escape_html_entities(&groups.join(", "))),
+        );
+    }
+    if let Some(version) = version {
+        definition.push(format!("Since <b>{}</b>", escape_html_entities(version)));
+    }
+
+    CrateDocument {
+        title: format!(
+            "Lint <a href=\"{}\">{}</a>",
+            url.replace('"', "&quot;"),
+            escape_html_entities(name)
+        ),
+        definition: Some(definition.join(" · ")),
+        portability_note: None,
+        stability_note: None,
+        deprecated: groups.iter().any(|group| group == "deprecated"),
+        description,
+        sections: sections
+            .into_iter()
+            .map(|(heading, paragraphs)| (heading, Article::Text(join_blocks(&paragraphs))))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_lint_names() {
+        assert_eq!(
+            normalize_name(" clippy::Needless-Borrow"),
+            (Some("clippy"), "needless_borrow".to_string())
+        );
+        assert_eq!(
+            normalize_name("unused-must-use"),
+            (None, "unused_must_use".to_string())
+        );
+    }
+
+    #[test]
+    fn split_rustc_listing_at_lints() {
+        let html = Html::parse_document(
+            r##"<main><h1 id="allowed-by-default-lints">Allowed-by-default Lints</h1>
+<p>These lints are all set to the 'allow' level by default.</p>
+<h2 id="box-pointers">box-pointers</h2>
+<p>The <code>box_pointers</code> lints use of the Box type.</p>
+<h3 id="example">Example</h3>
+<pre><code class="language-rust">struct Foo { x: Box&lt;isize&gt; }</code></pre>
+<h2 id="elided-lifetimes-in-paths">elided-lifetimes-in-paths</h2>
+<p>Hidden lifetime parameters.</p></main>"##,
+        );
+        let lints = parse_rustc_listing(&html);
+        let anchors: Vec<&str> = lints.iter().map(|(anchor, _)| anchor.as_str()).collect();
+        assert_eq!(anchors, vec!["box-pointers", "elided-lifetimes-in-paths"]);
+
+        let (anchor, blocks) = lints.into_iter().next().unwrap();
+        let document = lint_document(&anchor, "https://example.com", &[], "allow", None, blocks);
+        assert_eq!(
+            document.description,
+            "The <code>box_pointers</code> lints use of the Box type.\n"
+        );
+        assert_eq!(document.definition.as_deref(), Some("Level <b>allow</b>"));
+        assert_eq!(document.sections.len(), 1);
+        assert_eq!(document.sections[0].0, "Example");
+    }
+
+    #[test]
+    fn describe_clippy_lints_by_their_first_section() {
+        let blocks = markdown::blocks(
+            "### What it does\nChecks for borrows.\n\n### Example\n```rust\nlet x = &&a;\n```\n",
+        );
+        let document = lint_document(
+            "clippy::needless_borrow",
+            "https://example.com",
+            &["style".to_string()],
+            "warn",
+            Some("pre 1.29.0"),
+            blocks,
+        );
+        assert_eq!(document.description, "Checks for borrows.\n");
+        assert_eq!(
+            document.definition.as_deref(),
+            Some("Group <b>style</b> · Level <b>warn</b> · Since <b>pre 1.29.0</b>")
+        );
+        let headings: Vec<&str> = document
+            .sections
+            .iter()
+            .map(|(heading, _)| heading.as_str())
+            .collect();
+        assert_eq!(headings, vec!["Example"]);
+    }
+}
@@ -0,0 +1,121 @@
+use super::search::{node_text, parse_document_paragraph};
+use crate::util::TEXT_LIMIT;
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+use scraper::{ElementRef, Html};
+
+/// A top-level block of Markdown.
+pub(super) enum Block {
+    /// Text of a heading.
+    Heading(String),
+    /// A code block in Telegram HTML, with its info string, e.g. `compile_fail,E0382`.
+    Code { info: String, html: String },
+    /// Any other block in Telegram HTML.
+    Text(String),
+}
+
+/// Splits Markdown into its top-level blocks, converting each into Telegram HTML.
+pub(super) fn blocks(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut events = Vec::new();
+    let mut depth = 0;
+    let mut info = None;
+    let mut rust_code = false;
+    for event in Parser::new(markdown) {
+        match &event {
+            Event::Start(tag) => {
+                if depth == 0 {
+                    if let Tag::CodeBlock(kind) = tag {
+                        let language = match kind {
+                            CodeBlockKind::Fenced(language) => language.to_string(),
+                            CodeBlockKind::Indented => String::new(),
+                        };
+                        rust_code = is_rust(&language);
+                        info = Some(language);
+                    }
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+        let event = match event {
+            Event::Text(text) if rust_code => Event::Text(hide_lines(&text).into()),
+            // Attributes such as `compile_fail` would otherwise become the language
+            Event::Start(Tag::CodeBlock(_)) if rust_code => {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced("rust".into())))
+            }
+            event => event,
+        };
+        events.push(event);
+
+        if depth == 0 {
+            let mut html = String::new();
+            pulldown_cmark::html::push_html(&mut html, events.drain(..));
+            let info = info.take();
+            if let Some(block) = block(&html, info) {
+                blocks.push(block);
+            }
+            rust_code = false;
+        }
+    }
+    blocks
+}
+
+fn block(html: &str, info: Option<String>) -> Option<Block> {
+    let fragment = Html::parse_fragment(html);
+    let element = fragment
+        .root_element()
+        .children()
+        .filter_map(ElementRef::wrap)
+        .next()?;
+    match element.value().name() {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            Some(Block::Heading(node_text(element).trim().to_string()))
+        }
+        _ => {
+            let html = parse_document_paragraph(element)?;
+            match info {
+                Some(info) => Some(Block::Code { info, html }),
+                None => Some(Block::Text(html)),
+            }
+        }
+    }
+}
+
+/// Code blocks are Rust unless they are marked with another language, as in rustdoc.
+fn is_rust(info: &str) -> bool {
+    info.split(',').map(str::trim).all(|attribute| {
+        attribute.is_empty()
+            || attribute == "rust"
+            || attribute == "compile_fail"
+            || attribute == "should_panic"
+            || attribute == "no_run"
+            || attribute == "ignore"
+            || attribute.starts_with("edition")
+            || attribute.starts_with('E')
+    })
+}
+
+/// Removes the lines hidden by rustdoc, which start with `# `.
+fn hide_lines(code: &str) -> String {
+    code.split_inclusive('\n')
+        .filter(|line| {
+            let line = line.trim();
+            line != "#" && !line.starts_with("# ")
+        })
+        .collect()
+}
+
+/// Joins blocks of Telegram HTML into an article, cutting it short if it is too long.
+pub(super) fn join_blocks(blocks: &[String]) -> String {
+    let mut text = String::new();
+    for block in blocks {
+        if text.len() + block.len() > TEXT_LIMIT {
+            text.push_str("...");
+            break;
+        }
+        text.push_str(block);
+        text.push('\n');
+    }
+    text
+}
@@ -1,25 +1,46 @@
-use crate::util::{escape_html_entities, size_humanize, CallbackSession, CALLBACK_SESSIONS};
-use lazy_static::lazy_static;
+use crate::settings;
+use crate::storage::ExpiringTree;
+use crate::util::{
+    escape_html_entities, save_callback_session, size_humanize, split_version, CallbackSession,
+    DocumentSource, BUTTON_LIMIT, TEXT_LIMIT,
+};
 use log::{error, info};
-use std::collections::HashMap;
+use std::sync::Arc;
 use teloxide::prelude::*;
 use teloxide::requests::SendChatActionKind;
 use teloxide::types::{
-    CallbackQuery, ChatOrInlineMessage, InlineKeyboardButton, InlineKeyboardMarkup, ParseMode,
+    CallbackQuery, ChatOrInlineMessage, InlineKeyboardButton, InlineKeyboardButtonKind,
+    InlineKeyboardMarkup, InlineQuery, InlineQueryResult, InlineQueryResultArticle, InputFile,
+    InputMessageContent, ParseMode,
 };
-use tokio::sync::RwLock;
 
+mod advisory;
+mod books;
+mod chart;
 mod crates;
+mod error_index;
+mod lints;
+mod markdown;
+mod readme;
+mod releases;
 mod search;
+mod search_index;
+mod tree;
+mod unstable;
+mod watch;
+
+pub use books::Book;
 
 pub async fn crate_information(
     cx: DispatcherHandlerCx<Message>,
     args: Vec<String>,
 ) -> ResponseResult<()> {
-    const USAGE: &str = "<code>/crate [crate-name]</code>\n\
+    const USAGE: &str = "<code>/crate [crate-name][@version]</code>\n\
         Show information of a crate.\n\
         \n\
-        <code>[crate-name]</code>: the name of a crate";
+        <code>[crate-name]</code>: the name of a crate\n\
+        <code>[@version]</code>: (optional) a version or a semver requirement, e.g. <code>@1.0.100</code>, <code>@^1.0</code>";
+    const SUGGESTION_COUNT: usize = 6;
 
     if args.is_empty() {
         cx.reply_to(USAGE)
@@ -33,7 +54,8 @@ pub async fn crate_information(
             .await?;
         let crate_name = &args[0];
         let information = {
-            let result = crates::get_information(crate_name).await;
+            let (name, version) = split_version(crate_name);
+            let result = crates::get_information(name, version).await;
             match result {
                 Err(e) => {
                     error!(
@@ -49,131 +71,329 @@ pub async fn crate_information(
         if let Some(information) = information {
             info!("CrateInfo {{ Name = {} }}", crate_name);
 
-            let authors = {
-                let (primary_author, omitted) = information.owner.split_at(1);
-                let mut authors = format!(
-                    "<a href=\"{url}\">{name}</a>",
-                    name = primary_author[0]
-                        .name
-                        .as_ref()
-                        .unwrap_or(&"&lt;anonymous&gt;".to_string()),
-                    url = primary_author[0].url
-                );
-                if !omitted.is_empty() {
-                    authors.push_str(&format!(" and {} others", omitted.len()));
+            let session = CallbackSession::Crate {
+                name: information.name.clone(),
+                version: information.version.clone(),
+            };
+            let advisories = advisory::get_affecting(&information.name, &information.version).await;
+            let (info_text, markup) = information_card(information, &advisories);
+            let message = cx
+                .reply_to(info_text)
+                .parse_mode(ParseMode::HTML)
+                .reply_markup(markup)
+                .send()
+                .await?;
+            save_callback_session((message.chat_id(), message.id), &session);
+        } else {
+            let (name, _) = split_version(crate_name);
+            let suggestions = match crates::suggest_crates(name, SUGGESTION_COUNT).await {
+                Err(e) => {
+                    error!(
+                        "Failed to suggest crates for `{crate_name}`: {error}",
+                        crate_name = crate_name,
+                        error = e
+                    );
+                    Vec::new()
                 }
-                authors
+                Ok(suggestions) => suggestions,
             };
+            reply_not_found(
+                &cx,
+                "crate",
+                crate_name,
+                suggestions,
+                CallbackSession::CrateList,
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
 
-            let license = if let Some(license) = information.license {
-                format!("{} License", license)
-            } else {
-                "No License".into()
-            };
+fn information_card(
+    information: crates::Information,
+    advisories: &[Arc<advisory::Advisory>],
+) -> (String, InlineKeyboardMarkup) {
+    let authors = {
+        let (primary_author, omitted) = information.owner.split_at(1);
+        let mut authors = format!(
+            "<a href=\"{url}\">{name}</a>",
+            name = primary_author[0]
+                .name
+                .as_ref()
+                .unwrap_or(&"&lt;anonymous&gt;".to_string()),
+            url = primary_author[0].url
+        );
+        if !omitted.is_empty() {
+            authors.push_str(&format!(" and {} others", omitted.len()));
+        }
+        authors
+    };
 
-            let (updated_elapsed, created_elapsed) = {
-                let now = chrono::Utc::now();
-                (now - information.updated_at, now - information.created_at)
-            };
+    let license = if let Some(license) = information.license {
+        format!("{} License", license)
+    } else {
+        "No License".into()
+    };
 
-            let keywords = if information.keywords.is_empty() {
-                String::new()
-            } else {
-                format!(
-                    "\n\n<b>Keywords</b>\n<i>{}</i>",
-                    information.keywords.join(", ")
-                )
+    let (updated_elapsed, created_elapsed) = {
+        let now = chrono::Utc::now();
+        (now - information.updated_at, now - information.created_at)
+    };
+
+    let keywords = if information.keywords.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "\n\n<b>Keywords</b>\n<i>{}</i>",
+            information.keywords.join(", ")
+        )
+    };
+
+    let categories = if information.categories.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "\n\n<b>Categories</b>\n<i>{}</i>",
+            information.categories.join("\n")
+        )
+    };
+    use chrono_humanize::HumanTime;
+    let release = if information.version == information.newest_version {
+        String::new()
+    } else {
+        let published_elapsed = chrono::Utc::now() - information.published_at;
+        format!(
+            "\n📦 published at {published_at} ({published_elapsed}), latest is <i>{latest}</i>",
+            published_at = information.published_at.format("%Y-%m-%d %Z"),
+            published_elapsed = HumanTime::from(published_elapsed),
+            latest = information.newest_version,
+        )
+    };
+    let advisories_text = if advisories.is_empty() {
+        String::new()
+    } else {
+        let list: Vec<String> = advisories
+            .iter()
+            .map(|advisory| match &advisory.informational {
+                Some(kind) => format!("{} ({})", advisory.id, kind),
+                None => advisory.id.clone(),
+            })
+            .collect();
+        format!("\n🚨 <b>Affected by</b> {}", list.join(", "))
+    };
+    let info_text = format!(
+        "<b>{crate_name}</b> <i>{version}</i> ({size}B) by {authors}\n\
+        {license}{release}{advisories}\n\
+        \n\
+        {description}{keywords}{categories}\n\
+        \n\
+        ⬇️{recent} downloads recently ({total} total)\n\
+        📊{dependencies} dependencies ({dev_dependencies} for dev)\n\
+        🕒 updated at {updated_at} ({updated_elapsed})\n\
+        🕒 created at {created_at} ({created_elapsed})",
+        crate_name = information.name,
+        version = information.version,
+        size = size_humanize(information.crate_size),
+        authors = authors,
+        license = license,
+        release = release,
+        advisories = advisories_text,
+        description = escape_html_entities(&information.description),
+        updated_at = information.updated_at.format("%Y-%m-%d %Z"),
+        created_at = information.created_at.format("%Y-%m-%d %Z"),
+        recent = size_humanize(information.recent_downloads),
+        total = size_humanize(information.downloads),
+        dependencies = information.dependency_count,
+        dev_dependencies = information.dev_dependency_count,
+        keywords = keywords,
+        categories = categories,
+        updated_elapsed = HumanTime::from(updated_elapsed),
+        created_elapsed = HumanTime::from(created_elapsed),
+    );
+    let markup = {
+        let mut line = Vec::new();
+        if let Some(homepage) = information.homepage {
+            let button = InlineKeyboardButton::url("🏠 Home".into(), homepage);
+            line.push(button);
+        }
+        let default_docs = format!("https://docs.rs/{}", information.name);
+        let button = InlineKeyboardButton::url(
+            "📚 Docs".into(),
+            information.documentation.unwrap_or(default_docs),
+        );
+        line.push(button);
+        if let Some(repository) = information.repository {
+            let button = InlineKeyboardButton::url("📂 Repo".into(), repository);
+            line.push(button);
+        }
+        let actions = vec![
+            InlineKeyboardButton::callback("🗂 Versions".into(), "versions".into()),
+            InlineKeyboardButton::callback("📦 Dependencies".into(), "dependencies".into()),
+            InlineKeyboardButton::callback("📄 README".into(), "readme".into()),
+        ];
+        let mut inline_keyboard = vec![line, actions];
+        if !advisories.is_empty() {
+            inline_keyboard.push(vec![InlineKeyboardButton::callback(
+                "🚨 Advisories".into(),
+                "advisories".into(),
+            )]);
+        }
+        InlineKeyboardMarkup { inline_keyboard }
+    };
+    (info_text, markup)
+}
+
+pub async fn crate_information_callback(
+    cx: DispatcherHandlerCx<CallbackQuery>,
+    crate_name: String,
+    version: String,
+) -> ResponseResult<()> {
+    let message = cx.update.message.as_ref().unwrap();
+    let data = cx.update.data.as_ref().unwrap();
+
+    match data.as_str() {
+        "versions" => {
+            let versions = {
+                let result = crates::get_versions(&crate_name).await;
+                match result {
+                    Err(e) => {
+                        error!(
+                            "Failed to get versions of crate `{crate_name}`: {error}",
+                            crate_name = crate_name,
+                            error = e
+                        );
+                        return Ok(());
+                    }
+                    Ok(result) => result,
+                }
             };
+            if let Some(versions) = versions {
+                info!("Versions {{ Name = {}, From = Crate }}", crate_name);
 
-            let categories = if information.categories.is_empty() {
-                String::new()
-            } else {
-                format!(
-                    "\n\n<b>Categories</b>\n<i>{}</i>",
-                    information.categories.join("\n")
-                )
+                let (text, markup) = versions_page(&crate_name, &versions, 0);
+                let message = cx
+                    .bot
+                    .send_message(message.chat_id(), text)
+                    .parse_mode(ParseMode::HTML)
+                    .reply_to_message_id(message.id)
+                    .reply_markup(markup)
+                    .send()
+                    .await?;
+                save_callback_session(
+                    (message.chat_id(), message.id),
+                    &CallbackSession::Versions(crate_name),
+                );
+            }
+        }
+        "dependencies" => {
+            let dependencies = {
+                let result = crates::get_dependencies(&crate_name, Some(&version)).await;
+                match result {
+                    Err(e) => {
+                        error!(
+                            "Failed to get dependencies of crate `{crate_name}`: {error}",
+                            crate_name = crate_name,
+                            error = e
+                        );
+                        return Ok(());
+                    }
+                    Ok(result) => result,
+                }
             };
-            use chrono_humanize::HumanTime;
-            let info_text = format!(
-                "<b>{crate_name}</b> <i>{latest}</i> ({size}B) by {authors}\n\
-                {license}\n\
-                \n\
-                {description}{keywords}{categories}\n\
-                \n\
-                ⬇️{recent} downloads recently ({total} total)\n\
-                📊{dependencies} dependencies ({dev_dependencies} for dev)\n\
-                🕒 updated at {updated_at} ({updated_elapsed})\n\
-                🕒 created at {created_at} ({created_elapsed})",
-                crate_name = information.name,
-                latest = information.newest_version,
-                size = size_humanize(information.crate_size),
-                authors = authors,
-                license = license,
-                description = escape_html_entities(&information.description),
-                updated_at = information.updated_at.format("%Y-%m-%d %Z"),
-                created_at = information.created_at.format("%Y-%m-%d %Z"),
-                recent = size_humanize(information.recent_downloads),
-                total = size_humanize(information.downloads),
-                dependencies = information.dependency_count,
-                dev_dependencies = information.dev_dependency_count,
-                keywords = keywords,
-                categories = categories,
-                updated_elapsed = HumanTime::from(updated_elapsed),
-                created_elapsed = HumanTime::from(created_elapsed),
-            );
-            let markup = {
-                let mut line = Vec::new();
-                if let Some(homepage) = information.homepage {
-                    let button = InlineKeyboardButton::url("🏠 Home".into(), homepage);
-                    line.push(button);
-                }
-                let default_docs = format!("https://docs.rs/{}", crate_name);
-                let button = InlineKeyboardButton::url(
-                    "📚 Docs".into(),
-                    information.documentation.unwrap_or(default_docs),
+            if let Some(dependencies) = dependencies {
+                info!("Deps {{ Name = {}, From = Crate }}", crate_name);
+
+                let (text, names) = dependencies_text(&crate_name, &dependencies);
+                let message = cx
+                    .bot
+                    .send_message(message.chat_id(), text)
+                    .parse_mode(ParseMode::HTML)
+                    .reply_to_message_id(message.id)
+                    .reply_markup(crate_list_markup(&names))
+                    .send()
+                    .await?;
+                save_callback_session(
+                    (message.chat_id(), message.id),
+                    &CallbackSession::CrateList(names),
                 );
-                line.push(button);
-                if let Some(repository) = information.repository {
-                    let button = InlineKeyboardButton::url("📂 Repo".into(), repository);
-                    line.push(button);
+            }
+        }
+        "readme" => {
+            let document = {
+                let result = readme::get_readme(&crate_name, &version).await;
+                match result {
+                    Err(e) => {
+                        error!(
+                            "Failed to get readme of crate `{crate_name}`: {error}",
+                            crate_name = crate_name,
+                            error = e
+                        );
+                        return Ok(());
+                    }
+                    Ok(result) => result,
                 }
-                Some(InlineKeyboardMarkup {
-                    inline_keyboard: vec![line],
-                })
             };
-            let message = cx.reply_to(info_text).parse_mode(ParseMode::HTML);
-            if let Some(markup) = markup {
-                message.reply_markup(markup).send().await?;
+            if let Some(document) = document {
+                info!("Readme {{ Name = {}, Version = {} }}", crate_name, version);
+
+                let (text, markup) = document_message(&document);
+                let message = cx
+                    .bot
+                    .send_message(message.chat_id(), text)
+                    .parse_mode(ParseMode::HTML)
+                    .reply_to_message_id(message.id)
+                    .reply_markup(markup)
+                    .send()
+                    .await?;
+                save_document((message.chat_id(), message.id), &document);
+                let source = DocumentSource::Readme {
+                    name: crate_name,
+                    version,
+                };
+                save_callback_session(
+                    (message.chat_id(), message.id),
+                    &CallbackSession::Docs(source),
+                );
             } else {
-                message.send().await?;
+                cx.bot
+                    .send_message(message.chat_id(), "This version has no readme")
+                    .reply_to_message_id(message.id)
+                    .send()
+                    .await?;
             }
-        } else {
-            let not_found = format!(
-                "No crate `{crate_name}` has found",
-                crate_name = crate_name.replace('`', "\\`")
-            );
-            cx.answer(&not_found)
-                .parse_mode(ParseMode::MarkdownV2)
+        }
+        "advisories" => {
+            info!("Advisory {{ Name = {}, From = Crate }}", crate_name);
+
+            let advisories = advisory::get_affecting(&crate_name, &version).await;
+            let (text, ids) = advisories_text(&crate_name, Some(&version), &advisories);
+            let message = cx
+                .bot
+                .send_message(message.chat_id(), text)
+                .parse_mode(ParseMode::HTML)
+                .reply_to_message_id(message.id)
+                .reply_markup(crate_list_markup(&ids))
                 .send()
                 .await?;
+            save_callback_session(
+                (message.chat_id(), message.id),
+                &CallbackSession::Advisories(ids),
+            );
         }
+        _ => {}
     }
     Ok(())
 }
 
-lazy_static! {
-    static ref SEARCH_RESULT: RwLock<HashMap<(i64, i32), search::CrateDocument>> =
-        RwLock::new(HashMap::new());
-}
-
-pub async fn search_crate(
+pub async fn list_versions(
     cx: DispatcherHandlerCx<Message>,
     args: Vec<String>,
 ) -> ResponseResult<()> {
-    const USAGE: &str = "<code>/docs [path]</code>\n\
-        Show online documentation with specified path in a crate.\n\
+    const USAGE: &str = "<code>/versions [crate-name]</code>\n\
+        Show the release history of a crate.\n\
         \n\
-        <code>[path]</code>: the path to the item";
+        <code>[crate-name]</code>: the name of a crate";
 
     if args.is_empty() {
         cx.reply_to(USAGE)
@@ -185,14 +405,14 @@ pub async fn search_crate(
             .send_chat_action(cx.chat_id(), SendChatActionKind::Typing)
             .send()
             .await?;
-        let path = &args[0];
-        let document = {
-            let result = search::get_document(path).await;
+        let crate_name = &args[0];
+        let versions = {
+            let result = crates::get_versions(crate_name).await;
             match result {
                 Err(e) => {
-                    log::error!(
-                        "Failed to get information with path `{path}`: {error}",
-                        path = path,
+                    error!(
+                        "Failed to get versions of crate `{crate_name}`: {error}",
+                        crate_name = crate_name,
                         error = e
                     );
                     return Ok(());
@@ -200,73 +420,25 @@ pub async fn search_crate(
                 Ok(result) => result,
             }
         };
-        if let Some(document) = document {
-            info!("Docs {{ Path = {} }}", path);
-
-            let portability_text = if let Some(portability) = &document.portability_note {
-                format!("\n<i>{}</i>", portability)
-            } else {
-                String::new()
-            };
-
-            let stability_text = if let Some(stability) = &document.stability_note {
-                format!("\n<i>{}</i>", stability)
-            } else {
-                String::new()
-            };
-
-            let deprecated_text = if document.deprecated {
-                "<b>Deprecated</b>"
-            } else {
-                ""
-            };
-
-            let definition_text = if let Some(definition) = &document.definition {
-                format!("\n{}", definition)
-            } else {
-                String::new()
-            };
+        if let Some(versions) = versions {
+            info!("Versions {{ Name = {} }}", crate_name);
 
-            let text = format!(
-                "{title} {deprecated}{portability}{stability}{definition}\n\
-                \n\
-                {description}",
-                title = document.title,
-                deprecated = deprecated_text,
-                portability = portability_text,
-                stability = stability_text,
-                definition = definition_text,
-                description = document.description,
-            );
-            let markup = InlineKeyboardMarkup {
-                inline_keyboard: document
-                    .sections
-                    .iter()
-                    .enumerate()
-                    .map(|(i, (heading, _))| {
-                        vec![InlineKeyboardButton::callback(
-                            heading.clone(),
-                            i.to_string(),
-                        )]
-                    })
-                    .collect(),
-            };
+            let (text, markup) = versions_page(crate_name, &versions, 0);
             let message = cx
                 .reply_to(text)
                 .parse_mode(ParseMode::HTML)
                 .reply_markup(markup)
                 .send()
                 .await?;
-            {
-                let mut lock = SEARCH_RESULT.write().await;
-                lock.insert((message.chat_id(), message.id), document);
-            }
-            {
-                let mut lock = CALLBACK_SESSIONS.write().await;
-                lock.insert((message.chat_id(), message.id), CallbackSession::Docs);
-            }
+            save_callback_session(
+                (message.chat_id(), message.id),
+                &CallbackSession::Versions(crate_name.clone()),
+            );
         } else {
-            let not_found = format!("Could not find `{path}`", path = path.replace('`', "\\`"));
+            let not_found = format!(
+                "No crate `{crate_name}` has found",
+                crate_name = crate_name.replace('`', "\\`")
+            );
             cx.reply_to(&not_found)
                 .parse_mode(ParseMode::MarkdownV2)
                 .send()
@@ -276,56 +448,32 @@ pub async fn search_crate(
     Ok(())
 }
 
-pub async fn search_crate_callback(cx: DispatcherHandlerCx<CallbackQuery>) -> ResponseResult<()> {
+pub async fn list_versions_callback(
+    cx: DispatcherHandlerCx<CallbackQuery>,
+    crate_name: String,
+) -> ResponseResult<()> {
     let message = cx.update.message.as_ref().unwrap();
     let data = cx.update.data.as_ref().unwrap();
 
-    let lock = SEARCH_RESULT.read().await;
-    if let Some(document) = lock.get(&(message.chat_id(), message.id)) {
-        if let Some((heading, article)) = data
-            .parse::<usize>()
-            .ok()
-            .and_then(|i| document.sections.get(i))
-        {
-            info!("Docs {{ Title = {}, Data = {} }}", document.title, data);
-            let portability_text = if let Some(portability) = &document.portability_note {
-                format!("\n<i>{}</i>", portability)
-            } else {
-                String::new()
-            };
-
-            let stability_text = if let Some(stability) = &document.stability_note {
-                format!("\n<i>{}</i>", stability)
-            } else {
-                String::new()
-            };
-
-            let deprecated_text = if document.deprecated {
-                "<b>Deprecated</b>"
-            } else {
-                ""
-            };
-
-            let definition_text = if let Some(definition) = &document.definition {
-                format!("\n{}", definition)
-            } else {
-                String::new()
-            };
-
-            let text = format!(
-                "{title} {deprecated}{portability}{stability}{definition}\n\
-                \n\
-                <b>{heading}</b>\n\
-                {article}\n",
-                title = document.title,
-                deprecated = deprecated_text,
-                portability = portability_text,
-                stability = stability_text,
-                definition = definition_text,
-                heading = heading,
-                article = article_to_text(article),
-            );
+    if let Ok(page) = data.parse::<usize>() {
+        let versions = {
+            let result = crates::get_versions(&crate_name).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get versions of crate `{crate_name}`: {error}",
+                        crate_name = crate_name,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(versions) = versions {
+            info!("Versions {{ Name = {}, Page = {} }}", crate_name, page);
 
+            let (text, markup) = versions_page(&crate_name, &versions, page);
             cx.bot
                 .edit_message_text(
                     ChatOrInlineMessage::Chat {
@@ -335,7 +483,70 @@ pub async fn search_crate_callback(cx: DispatcherHandlerCx<CallbackQuery>) -> Re
                     text,
                 )
                 .parse_mode(ParseMode::HTML)
-                .reply_markup(message.reply_markup().unwrap().clone())
+                .reply_markup(markup)
+                .send()
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn list_dependencies(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/deps [crate-name][@version]</code>\n\
+        List the dependencies of a crate.\n\
+        \n\
+        <code>[crate-name]</code>: the name of a crate\n\
+        <code>[@version]</code>: (optional) a version or a semver requirement";
+
+    if args.is_empty() {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        cx.bot
+            .send_chat_action(cx.chat_id(), SendChatActionKind::Typing)
+            .send()
+            .await?;
+        let (crate_name, version) = split_version(&args[0]);
+        let dependencies = {
+            let result = crates::get_dependencies(crate_name, version).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get dependencies of crate `{crate_name}`: {error}",
+                        crate_name = args[0],
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(dependencies) = dependencies {
+            info!("Deps {{ Name = {} }}", args[0]);
+
+            let (text, names) = dependencies_text(crate_name, &dependencies);
+            let message = cx
+                .reply_to(text)
+                .parse_mode(ParseMode::HTML)
+                .reply_markup(crate_list_markup(&names))
+                .send()
+                .await?;
+            save_callback_session(
+                (message.chat_id(), message.id),
+                &CallbackSession::CrateList(names),
+            );
+        } else {
+            let not_found = format!(
+                "No crate `{crate_name}` has found",
+                crate_name = args[0].replace('`', "\\`")
+            );
+            cx.reply_to(&not_found)
+                .parse_mode(ParseMode::MarkdownV2)
                 .send()
                 .await?;
         }
@@ -343,6 +554,2623 @@ pub async fn search_crate_callback(cx: DispatcherHandlerCx<CallbackQuery>) -> Re
     Ok(())
 }
 
+/// Replies that nothing named `name` has been found, offering the suggestions, if any,
+/// as buttons handled by the callback session made of them.
+async fn reply_not_found(
+    cx: &DispatcherHandlerCx<Message>,
+    kind: &str,
+    name: &str,
+    suggestions: Vec<String>,
+    session: fn(Vec<String>) -> CallbackSession,
+) -> ResponseResult<()> {
+    let not_found = not_found_text(kind, name);
+    if suggestions.is_empty() {
+        cx.reply_to(not_found)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        info!("Suggestions {{ Kind = {}, Name = {} }}", kind, name);
+
+        let markup = InlineKeyboardMarkup {
+            inline_keyboard: suggestions
+                .iter()
+                .enumerate()
+                .map(|(i, suggestion)| {
+                    vec![InlineKeyboardButton::callback(
+                        suggestion.clone(),
+                        i.to_string(),
+                    )]
+                })
+                .collect(),
+        };
+        let message = cx
+            .reply_to(format!("{}\nDid you mean?", not_found))
+            .parse_mode(ParseMode::HTML)
+            .reply_markup(markup)
+            .send()
+            .await?;
+        save_callback_session((message.chat_id(), message.id), &session(suggestions));
+    }
+    Ok(())
+}
+
+fn not_found_text(kind: &str, name: &str) -> String {
+    format!(
+        "No {kind} <code>{name}</code> has been found",
+        kind = kind,
+        name = escape_html_entities(name)
+    )
+}
+
+/// Lays out a button per crate, three in a row.
+fn crate_list_markup(names: &[String]) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup {
+        inline_keyboard: names
+            .chunks(3)
+            .enumerate()
+            .map(|(row, chunk)| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(column, name)| {
+                        InlineKeyboardButton::callback(name.clone(), (row * 3 + column).to_string())
+                    })
+                    .collect()
+            })
+            .collect(),
+    }
+}
+
+/// Renders the dependency list grouped by kind.
+/// Also returns the names of the listed dependencies in order, without duplicates.
+fn dependencies_text(
+    crate_name: &str,
+    dependencies: &crates::Dependencies,
+) -> (String, Vec<String>) {
+    let mut text = format!(
+        "<b>{crate_name}</b> <i>{version}</i> dependencies\n",
+        crate_name = crate_name,
+        version = dependencies.version,
+    );
+    let mut names: Vec<String> = Vec::new();
+    let mut omitted = 0;
+    for (kind, heading) in &[("normal", "Normal"), ("build", "Build"), ("dev", "Dev")] {
+        let mut group = dependencies
+            .dependencies
+            .iter()
+            .filter(|d| d.kind == *kind)
+            .peekable();
+        if group.peek().is_none() {
+            continue;
+        }
+        text.push_str(&format!("\n<b>{}</b>\n", heading));
+        for dependency in group {
+            let optional = if dependency.optional {
+                let enabling = dependencies.enabling_features(&dependency.name);
+                if enabling.is_empty() {
+                    " <i>optional</i>".to_string()
+                } else {
+                    format!(" <i>optional, enabled by {}</i>", enabling.join(", "))
+                }
+            } else {
+                String::new()
+            };
+            let features = match (dependency.features.is_empty(), dependency.default_features) {
+                (true, true) => String::new(),
+                (true, false) => "\n    no default features".to_string(),
+                (false, true) => format!("\n    features: {}", dependency.features.join(", ")),
+                (false, false) => format!(
+                    "\n    features: {} (no default features)",
+                    dependency.features.join(", ")
+                ),
+            };
+            let target = if let Some(target) = &dependency.target {
+                format!(
+                    "\n    target: <code>{}</code>",
+                    escape_html_entities(target)
+                )
+            } else {
+                String::new()
+            };
+            let entry = format!(
+                "• <code>{name}</code> {req}{optional}{features}{target}\n",
+                name = dependency.name,
+                req = escape_html_entities(&dependency.req),
+                optional = optional,
+                features = features,
+                target = target,
+            );
+            if text.len() + entry.len() > TEXT_LIMIT {
+                omitted += 1;
+                continue;
+            }
+            text.push_str(&entry);
+            if names.len() < BUTTON_LIMIT && !names.contains(&dependency.name) {
+                names.push(dependency.name.clone());
+            }
+        }
+    }
+    if dependencies.dependencies.is_empty() {
+        text.push_str("\nNo dependencies");
+    }
+    if omitted > 0 {
+        text.push_str(&format!("\n...and {} more", omitted));
+    }
+    (text, names)
+}
+
+pub async fn list_features(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/features [crate-name][@version]</code>\n\
+        Show the feature flags of a crate.\n\
+        \n\
+        <code>[crate-name]</code>: the name of a crate\n\
+        <code>[@version]</code>: (optional) a version or a semver requirement";
+
+    if args.is_empty() {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        cx.bot
+            .send_chat_action(cx.chat_id(), SendChatActionKind::Typing)
+            .send()
+            .await?;
+        let (crate_name, version) = split_version(&args[0]);
+        let dependencies = {
+            let result = crates::get_dependencies(crate_name, version).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get features of crate `{crate_name}`: {error}",
+                        crate_name = args[0],
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(dependencies) = dependencies {
+            info!("Features {{ Name = {} }}", args[0]);
+
+            let text = features_text(crate_name, &dependencies);
+            let message = cx
+                .reply_to(text)
+                .parse_mode(ParseMode::HTML)
+                .reply_markup(features_markup(&dependencies))
+                .send()
+                .await?;
+            save_callback_session(
+                (message.chat_id(), message.id),
+                &CallbackSession::Features {
+                    name: crate_name.to_string(),
+                    version: dependencies.version,
+                },
+            );
+        } else {
+            let not_found = format!(
+                "No crate `{crate_name}` has found",
+                crate_name = args[0].replace('`', "\\`")
+            );
+            cx.reply_to(&not_found)
+                .parse_mode(ParseMode::MarkdownV2)
+                .send()
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn list_features_callback(
+    cx: DispatcherHandlerCx<CallbackQuery>,
+    crate_name: String,
+    version: String,
+) -> ResponseResult<()> {
+    let message = cx.update.message.as_ref().unwrap();
+    let data = cx.update.data.as_ref().unwrap();
+
+    let dependencies = {
+        let result = crates::get_dependencies(&crate_name, Some(&version)).await;
+        match result {
+            Err(e) => {
+                error!(
+                    "Failed to get features of crate `{crate_name}`: {error}",
+                    crate_name = crate_name,
+                    error = e
+                );
+                return Ok(());
+            }
+            Ok(result) => result,
+        }
+    };
+    if let Some(dependencies) = dependencies {
+        let text = if data == "overview" {
+            features_text(&crate_name, &dependencies)
+        } else if let Some(feature) = data
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| dependencies.all_features().keys().nth(i).cloned())
+        {
+            let closure = dependencies.feature_closure(&feature);
+            let list = |items: &std::collections::BTreeSet<String>| {
+                if items.is_empty() {
+                    "<i>none</i>".to_string()
+                } else {
+                    items
+                        .iter()
+                        .map(|item| format!("<code>{}</code>", item))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            };
+            format!(
+                "<b>{crate_name}</b> <i>{version}</i> feature <code>{feature}</code>\n\
+                \n\
+                <b>Features</b>\n{features}\n\
+                \n\
+                <b>Optional dependencies</b>\n{dependencies}\n\
+                \n\
+                <b>Dependency features</b>\n{dependency_features}",
+                crate_name = crate_name,
+                version = version,
+                feature = feature,
+                features = list(&closure.features),
+                dependencies = list(&closure.dependencies),
+                dependency_features = list(&closure.dependency_features),
+            )
+        } else {
+            return Ok(());
+        };
+        info!("Features {{ Name = {}, Data = {} }}", crate_name, data);
+
+        cx.bot
+            .edit_message_text(
+                ChatOrInlineMessage::Chat {
+                    chat_id: message.chat_id().into(),
+                    message_id: message.id,
+                },
+                text,
+            )
+            .parse_mode(ParseMode::HTML)
+            .reply_markup(features_markup(&dependencies))
+            .send()
+            .await?;
+    }
+    Ok(())
+}
+
+fn features_text(crate_name: &str, dependencies: &crates::Dependencies) -> String {
+    let features = dependencies.all_features();
+    let default = dependencies.feature_closure("default").features;
+    let mut text = format!(
+        "<b>{crate_name}</b> <i>{version}</i> features\n\
+        ⭐ enabled by default\n\n",
+        crate_name = crate_name,
+        version = dependencies.version,
+    );
+    if features.is_empty() {
+        text.push_str("No features");
+    }
+    let mut omitted = 0;
+    for (feature, enables) in &features {
+        let mark = if feature == "default" || default.contains(feature) {
+            "⭐"
+        } else {
+            "•"
+        };
+        let entry = if enables.is_empty() {
+            format!("{} <code>{}</code>\n", mark, feature)
+        } else {
+            format!(
+                "{} <code>{}</code> → {}\n",
+                mark,
+                feature,
+                escape_html_entities(&enables.join(", "))
+            )
+        };
+        if text.len() + entry.len() > TEXT_LIMIT {
+            omitted += 1;
+            continue;
+        }
+        text.push_str(&entry);
+    }
+    if omitted > 0 {
+        text.push_str(&format!("...and {} more", omitted));
+    }
+    text
+}
+
+/// A button per feature carrying its index in name order, with one returning to the overview.
+fn features_markup(dependencies: &crates::Dependencies) -> InlineKeyboardMarkup {
+    let features: Vec<String> = dependencies
+        .all_features()
+        .keys()
+        .take(BUTTON_LIMIT)
+        .cloned()
+        .collect();
+    let mut inline_keyboard = vec![vec![InlineKeyboardButton::callback(
+        "📋 Overview".into(),
+        "overview".into(),
+    )]];
+    inline_keyboard.extend(features.chunks(3).enumerate().map(|(row, chunk)| {
+        chunk
+            .iter()
+            .enumerate()
+            .map(|(column, feature)| {
+                InlineKeyboardButton::callback(feature.clone(), (row * 3 + column).to_string())
+            })
+            .collect()
+    }));
+    InlineKeyboardMarkup { inline_keyboard }
+}
+
+pub async fn dependency_tree(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/tree [crate-name][@version]</code>\n\
+        Show the resolved dependency tree of a crate with default features.\n\
+        \n\
+        <code>[crate-name]</code>: the name of a crate\n\
+        <code>[@version]</code>: (optional) a version or a semver requirement";
+
+    if args.is_empty() {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        cx.bot
+            .send_chat_action(cx.chat_id(), SendChatActionKind::Typing)
+            .send()
+            .await?;
+        let (crate_name, version) = split_version(&args[0]);
+        let tree = {
+            let result = tree::get_tree(crate_name, version).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to resolve dependency tree of crate `{crate_name}`: {error}",
+                        crate_name = args[0],
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(tree) = tree {
+            info!("Tree {{ Name = {} }}", args[0]);
+
+            let (rendered, cut) = tree.render(TEXT_LIMIT);
+            let cut_text = if cut { "...\n" } else { "" };
+            let duplicates = tree.duplicates();
+            let duplicates_text = if duplicates.is_empty() {
+                String::new()
+            } else {
+                let list: Vec<String> = duplicates
+                    .iter()
+                    .map(|(name, versions)| format!("{} ({})", name, versions.join(", ")))
+                    .collect();
+                format!("\n‼ <b>Duplicates</b>: {}", list.join(", "))
+            };
+            let truncated_text = if tree.truncated {
+                "\n<i>The graph is too large and only partially resolved</i>"
+            } else {
+                ""
+            };
+            let unavailable_text = if tree.unavailable.is_empty() {
+                String::new()
+            } else {
+                let names: Vec<&str> = tree.unavailable.iter().map(String::as_str).collect();
+                format!(
+                    "\n<i>Could not fetch {}, which are left out</i>",
+                    escape_html_entities(&names.join(", "))
+                )
+            };
+            let text = format!(
+                "<pre>{tree}{cut}</pre>\n\
+                📊{count} unique crates{duplicates}{truncated}{unavailable}",
+                tree = escape_html_entities(&rendered),
+                cut = cut_text,
+                count = tree.unique_count(),
+                duplicates = duplicates_text,
+                truncated = truncated_text,
+                unavailable = unavailable_text,
+            );
+            cx.reply_to(text).parse_mode(ParseMode::HTML).send().await?;
+        } else {
+            let not_found = format!(
+                "No crate `{crate_name}` has found",
+                crate_name = args[0].replace('`', "\\`")
+            );
+            cx.reply_to(&not_found)
+                .parse_mode(ParseMode::MarkdownV2)
+                .send()
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn compare_crates(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/compare [crate-name] [crate-name]...</code>\n\
+        Compare crates side by side.\n\
+        \n\
+        <code>[crate-name]</code>: the names of two to five crates";
+    const MAX_CRATES: usize = 5;
+
+    if args.len() < 2 || args.len() > MAX_CRATES {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        cx.bot
+            .send_chat_action(cx.chat_id(), SendChatActionKind::Typing)
+            .send()
+            .await?;
+        let results = futures::future::join_all(
+            args.iter()
+                .map(|crate_name| crates::get_information(crate_name, None)),
+        )
+        .await;
+        let mut found = Vec::new();
+        let mut not_found = Vec::new();
+        for (crate_name, result) in args.iter().zip(results) {
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get information of crate `{crate_name}`: {error}",
+                        crate_name = crate_name,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(Some(information)) => found.push(information),
+                Ok(None) => not_found.push(crate_name),
+            }
+        }
+        if !not_found.is_empty() {
+            let not_found = not_found
+                .iter()
+                .map(|crate_name| {
+                    format!(
+                        "No crate `{crate_name}` has found",
+                        crate_name = crate_name.replace('`', "\\`")
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            cx.reply_to(&not_found)
+                .parse_mode(ParseMode::MarkdownV2)
+                .send()
+                .await?;
+            return Ok(());
+        }
+        info!("Compare {{ Names = {} }}", args.join(", "));
+
+        use chrono_humanize::HumanTime;
+        let now = chrono::Utc::now();
+        let rows = [
+            comparison_row("📦 Latest version", &found, None, |i| {
+                (0, i.newest_version.clone())
+            }),
+            comparison_row("📜 License", &found, None, |i| {
+                (0, i.license.clone().unwrap_or_else(|| "None".into()))
+            }),
+            comparison_row("⬇️ Downloads", &found, Some(true), |i| {
+                (i.downloads as i64, size_humanize(i.downloads))
+            }),
+            comparison_row("⬇️ Recent downloads", &found, Some(true), |i| {
+                (i.recent_downloads as i64, size_humanize(i.recent_downloads))
+            }),
+            comparison_row("📊 Dependencies", &found, Some(false), |i| {
+                (
+                    i.dependency_count as i64,
+                    format!(
+                        "{} ({} for dev)",
+                        i.dependency_count, i.dev_dependency_count
+                    ),
+                )
+            }),
+            comparison_row("💾 Crate size", &found, Some(false), |i| {
+                (
+                    i.crate_size as i64,
+                    format!("{}B", size_humanize(i.crate_size)),
+                )
+            }),
+            comparison_row("🕒 Last update", &found, Some(true), |i| {
+                (
+                    i.updated_at.timestamp(),
+                    HumanTime::from(i.updated_at - now).to_string(),
+                )
+            }),
+            comparison_row("👥 Owners", &found, Some(true), |i| {
+                (i.owner.len() as i64, i.owner.len().to_string())
+            }),
+        ];
+        let names: Vec<String> = found
+            .iter()
+            .map(|information| format!("<b>{}</b>", information.name))
+            .collect();
+        let text = format!(
+            "{names}\n\n{rows}",
+            names = names.join(" vs "),
+            rows = rows.join("\n\n"),
+        );
+        cx.reply_to(text).parse_mode(ParseMode::HTML).send().await?;
+    }
+    Ok(())
+}
+
+/// Renders a row of the comparison, a line per crate.
+/// `value` returns the value to rank by and its text.
+/// The best ranked crate is marked if `higher_is_better` is given.
+fn comparison_row(
+    heading: &str,
+    crates: &[crates::Information],
+    higher_is_better: Option<bool>,
+    value: impl Fn(&crates::Information) -> (i64, String),
+) -> String {
+    let values: Vec<(i64, String)> = crates.iter().map(value).collect();
+    let best = match higher_is_better {
+        Some(true) => values.iter().map(|(rank, _)| *rank).max(),
+        Some(false) => values.iter().map(|(rank, _)| *rank).min(),
+        None => None,
+    };
+    let lines: Vec<String> = crates
+        .iter()
+        .zip(values.iter())
+        .map(|(information, (rank, text))| {
+            let winner = if Some(*rank) == best { " 🏆" } else { "" };
+            format!(
+                "{name}: <i>{text}</i>{winner}",
+                name = information.name,
+                text = escape_html_entities(text),
+                winner = winner,
+            )
+        })
+        .collect();
+    format!("<b>{}</b>\n{}", heading, lines.join("\n"))
+}
+
+pub async fn download_statistics(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/downloads [crate-name] [major]</code>\n\
+        Show the daily downloads of a crate over the last 90 days as a chart.\n\
+        \n\
+        <code>[crate-name]</code>: the name of a crate\n\
+        <code>[major]</code>: (optional) stack the downloads by major version";
+    const DAYS: usize = 90;
+
+    if args.is_empty() || args.len() > 2 || args.get(1).is_some_and(|arg| arg != "major") {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        cx.bot
+            .send_chat_action(cx.chat_id(), SendChatActionKind::UploadPhoto)
+            .send()
+            .await?;
+        let crate_name = &args[0];
+        let by_major = args.len() == 2;
+        let downloads = {
+            let result = crates::get_downloads(crate_name, DAYS).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get downloads of crate `{crate_name}`: {error}",
+                        crate_name = crate_name,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(downloads) = downloads {
+            info!(
+                "Downloads {{ Name = {}, Major = {} }}",
+                crate_name, by_major
+            );
+
+            let series = if by_major {
+                downloads.by_major()
+            } else {
+                vec![("all versions".to_string(), downloads.total())]
+            };
+            let caption = format!("{} downloads in the last {} days", crate_name, DAYS);
+            let chart = {
+                let result = chart::render_downloads(&caption, &downloads.dates, &series);
+                match result {
+                    Err(e) => {
+                        error!(
+                            "Failed to render downloads of crate `{crate_name}`: {error}",
+                            crate_name = crate_name,
+                            error = e
+                        );
+                        return Ok(());
+                    }
+                    Ok(result) => result,
+                }
+            };
+            let total: usize = downloads.total().iter().sum();
+            let text = format!(
+                "<b>{crate_name}</b> ⬇️{total} downloads in the last {days} days",
+                crate_name = crate_name,
+                total = size_humanize(total),
+                days = DAYS,
+            );
+            // teloxide uploads photos from files only, so the chart goes through a temporary one
+            let path = std::env::temp_dir().join(format!(
+                "downloads-{}-{}.png",
+                cx.chat_id(),
+                cx.update.id
+            ));
+            if let Err(e) = tokio::fs::write(&path, chart).await {
+                error!(
+                    "Failed to write downloads chart to `{path}`: {error}",
+                    path = path.display(),
+                    error = e
+                );
+                return Ok(());
+            }
+            let sent = cx
+                .bot
+                .send_photo(cx.chat_id(), InputFile::File(path.clone()))
+                .caption(text)
+                .parse_mode(ParseMode::HTML)
+                .reply_to_message_id(cx.update.id)
+                .send()
+                .await;
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                error!(
+                    "Failed to remove downloads chart `{path}`: {error}",
+                    path = path.display(),
+                    error = e
+                );
+            }
+            sent?;
+        } else {
+            let not_found = format!(
+                "No crate `{crate_name}` has found",
+                crate_name = crate_name.replace('`', "\\`")
+            );
+            cx.reply_to(&not_found)
+                .parse_mode(ParseMode::MarkdownV2)
+                .send()
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Indexes the RustSec advisory database cloned at `path`.
+pub async fn load_advisories(path: &std::path::Path) {
+    match advisory::load(path).await {
+        Ok(count) => info!("Loaded {} advisories from `{}`", count, path.display()),
+        Err(e) => error!(
+            "Failed to load advisories from `{path}`: {error}",
+            path = path.display(),
+            error = e
+        ),
+    }
+}
+
+/// Moves the subscriptions saved at `path` by an older version of the bot into the storage.
+pub async fn import_subscriptions(path: &std::path::Path) {
+    match watch::import(path).await {
+        Ok(Some(count)) => info!("Imported {} subscriptions from `{}`", count, path.display()),
+        Ok(None) => {}
+        Err(e) => error!(
+            "Failed to import subscriptions from `{path}`: {error}",
+            path = path.display(),
+            error = e
+        ),
+    }
+}
+
+/// Indexes the rustc error code explanations in the snapshot at `path`.
+pub async fn load_error_index(path: &std::path::Path) {
+    match error_index::load(path).await {
+        Ok(count) => info!(
+            "Loaded {} error code explanations from `{}`",
+            count,
+            path.display()
+        ),
+        Err(e) => error!(
+            "Failed to load error code explanations from `{path}`: {error}",
+            path = path.display(),
+            error = e
+        ),
+    }
+}
+
+pub async fn explain_error(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/explain [code]</code>\n\
+        Show the explanation of a rustc error code.\n\
+        \n\
+        <code>[code]</code>: the error code, e.g. <code>E0382</code>";
+
+    if args.is_empty() {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else if let Some(document) = error_index::get_explanation(&args[0]).await {
+        info!("Explain {{ Code = {} }}", args[0]);
+
+        let (text, markup) = document_message(&document);
+        let message = cx
+            .reply_to(text)
+            .parse_mode(ParseMode::HTML)
+            .reply_markup(markup)
+            .send()
+            .await?;
+        save_document((message.chat_id(), message.id), &document);
+        save_callback_session(
+            (message.chat_id(), message.id),
+            &CallbackSession::Docs(DocumentSource::Explain(args[0].clone())),
+        );
+    } else {
+        cx.reply_to(not_found_text("error code", &args[0]))
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn show_advisories(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/advisory [crate-name][@version]</code>\n\
+        List the RustSec security advisories of a crate.\n\
+        \n\
+        <code>[crate-name]</code>: the name of a crate\n\
+        <code>[@version]</code>: (optional) list only the advisories affecting this version\n\
+        \n\
+        <code>/advisory [id]</code>\n\
+        Show a RustSec security advisory, e.g. <code>RUSTSEC-2020-0001</code>";
+
+    if args.is_empty() {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else if args[0].to_uppercase().starts_with("RUSTSEC-") {
+        let id = &args[0];
+        if let Some(advisory) = advisory::get_advisory(id).await {
+            info!("Advisory {{ Id = {} }}", id);
+
+            let (text, markup) = advisory_message(&advisory);
+            cx.reply_to(text)
+                .parse_mode(ParseMode::HTML)
+                .reply_markup(markup)
+                .send()
+                .await?;
+        } else {
+            let not_found = format!("No advisory `{id}` has found", id = id.replace('`', "\\`"));
+            cx.reply_to(&not_found)
+                .parse_mode(ParseMode::MarkdownV2)
+                .send()
+                .await?;
+        }
+    } else {
+        let (crate_name, version) = split_version(&args[0]);
+        info!("Advisory {{ Name = {} }}", args[0]);
+
+        let advisories = match version {
+            Some(version) => advisory::get_affecting(crate_name, version).await,
+            None => advisory::get_advisories(crate_name).await,
+        };
+        let (text, ids) = advisories_text(crate_name, version, &advisories);
+        let message = cx
+            .reply_to(text)
+            .parse_mode(ParseMode::HTML)
+            .reply_markup(crate_list_markup(&ids))
+            .send()
+            .await?;
+        save_callback_session(
+            (message.chat_id(), message.id),
+            &CallbackSession::Advisories(ids),
+        );
+    }
+    Ok(())
+}
+
+pub async fn advisory_list_callback(
+    cx: DispatcherHandlerCx<CallbackQuery>,
+    ids: Vec<String>,
+) -> ResponseResult<()> {
+    let message = cx.update.message.as_ref().unwrap();
+    let data = cx.update.data.as_ref().unwrap();
+
+    if let Some(id) = data.parse::<usize>().ok().and_then(|i| ids.get(i)) {
+        if let Some(advisory) = advisory::get_advisory(id).await {
+            info!("Advisory {{ Id = {}, From = List }}", id);
+
+            let (text, markup) = advisory_message(&advisory);
+            cx.bot
+                .send_message(message.chat_id(), text)
+                .parse_mode(ParseMode::HTML)
+                .reply_to_message_id(message.id)
+                .reply_markup(markup)
+                .send()
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Renders the list of advisories of a crate.
+/// Also returns the ids of the listed advisories in order.
+fn advisories_text(
+    crate_name: &str,
+    version: Option<&str>,
+    advisories: &[Arc<advisory::Advisory>],
+) -> (String, Vec<String>) {
+    let heading = match version {
+        Some(version) => format!(
+            "<b>{}</b> <i>{}</i> advisories",
+            escape_html_entities(crate_name),
+            escape_html_entities(version)
+        ),
+        None => format!("<b>{}</b> advisories", escape_html_entities(crate_name)),
+    };
+    if advisories.is_empty() {
+        return (format!("{}\n\nNo advisories", heading), Vec::new());
+    }
+    let mut text = heading;
+    text.push('\n');
+    let mut ids = Vec::new();
+    let mut omitted = 0;
+    for advisory in advisories {
+        let mark = if advisory.withdrawn.is_some() {
+            "🗑"
+        } else if advisory.informational.is_some() {
+            "⚠️"
+        } else {
+            "🚨"
+        };
+        let patched = if advisory.patched.is_empty() {
+            "no patched versions".to_string()
+        } else {
+            format!("patched: {}", advisory.patched.join(", "))
+        };
+        let entry = format!(
+            "\n{mark} <b>{id}</b> {title}\n\
+            🕒 {date}, {patched}\n",
+            mark = mark,
+            id = advisory.id,
+            title = escape_html_entities(&advisory.title),
+            date = advisory.date,
+            patched = escape_html_entities(&patched),
+        );
+        if text.len() + entry.len() > TEXT_LIMIT {
+            omitted += 1;
+            continue;
+        }
+        text.push_str(&entry);
+        ids.push(advisory.id.clone());
+    }
+    if omitted > 0 {
+        text.push_str(&format!("\n...and {} more", omitted));
+    }
+    (text, ids)
+}
+
+fn advisory_message(advisory: &advisory::Advisory) -> (String, InlineKeyboardMarkup) {
+    let kind = match (&advisory.withdrawn, &advisory.informational) {
+        (Some(withdrawn), _) => format!("🗑 withdrawn at {}", withdrawn),
+        (None, Some(informational)) => format!("⚠️ {}", informational),
+        (None, None) => "🚨 vulnerability".to_string(),
+    };
+    let list = |heading: &str, items: &[String]| {
+        if items.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "\n<b>{}</b>: {}",
+                heading,
+                escape_html_entities(&items.join(", "))
+            )
+        }
+    };
+    let mut description = escape_html_entities(&advisory.description);
+    if description.len() > TEXT_LIMIT {
+        let mut end = TEXT_LIMIT;
+        while !description.is_char_boundary(end) {
+            end -= 1;
+        }
+        description.truncate(end);
+        description.push_str("...");
+    }
+    let text = format!(
+        "<b>{id}</b> {title}\n\
+        <i>{package}</i>, {kind}, reported at {date}\
+        {aliases}{categories}{patched}{unaffected}\n\
+        \n\
+        {description}",
+        id = advisory.id,
+        title = escape_html_entities(&advisory.title),
+        package = advisory.package,
+        kind = kind,
+        date = advisory.date,
+        aliases = list("Aliases", &advisory.aliases),
+        categories = list("Categories", &advisory.categories),
+        patched = list("Patched", &advisory.patched),
+        unaffected = list("Unaffected", &advisory.unaffected),
+        description = description,
+    );
+    let mut line = vec![InlineKeyboardButton::url(
+        "🛡 RustSec".into(),
+        format!("https://rustsec.org/advisories/{}.html", advisory.id),
+    )];
+    if let Some(url) = &advisory.url {
+        line.push(InlineKeyboardButton::url("🔗 Details".into(), url.clone()));
+    }
+    let markup = InlineKeyboardMarkup {
+        inline_keyboard: vec![line],
+    };
+    (text, markup)
+}
+
+/// Checks the watched crates for new releases and yanks periodically,
+/// notifying the chats watching them.
+pub async fn watch_releases(bot: Arc<Bot>) {
+    const INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+    loop {
+        tokio::time::delay_for(INTERVAL).await;
+        let watched = match watch::get_watched_crates() {
+            Err(e) => {
+                error!("Failed to load watched crates: {error}", error = e);
+                continue;
+            }
+            Ok(watched) => watched,
+        };
+        for crate_name in watched {
+            let versions = match crates::get_versions(&crate_name).await {
+                Err(e) => {
+                    error!(
+                        "Failed to get versions of crate `{crate_name}`: {error}",
+                        crate_name = crate_name,
+                        error = e
+                    );
+                    continue;
+                }
+                Ok(Some(versions)) => versions,
+                Ok(None) => continue,
+            };
+            let notifications = match watch::update(&crate_name, &versions).await {
+                Err(e) => {
+                    error!(
+                        "Failed to save subscriptions of crate `{crate_name}`: {error}",
+                        crate_name = crate_name,
+                        error = e
+                    );
+                    continue;
+                }
+                Ok(notifications) => notifications,
+            };
+            for notification in notifications {
+                info!(
+                    "Notify {{ Chat = {}, Name = {}, Version = {}, Yanked = {} }}",
+                    notification.chat_id, crate_name, notification.version, notification.yanked
+                );
+
+                let text = if notification.yanked {
+                    format!(
+                        "🗑 <b>{}</b> <i>{}</i> has been yanked",
+                        crate_name, notification.version
+                    )
+                } else {
+                    format!(
+                        "🎉 <b>{}</b> <i>{}</i> has been released",
+                        crate_name, notification.version
+                    )
+                };
+                let markup = InlineKeyboardMarkup {
+                    inline_keyboard: vec![vec![InlineKeyboardButton::url(
+                        "📦 crates.io".into(),
+                        format!(
+                            "https://crates.io/crates/{}/{}",
+                            crate_name, notification.version
+                        ),
+                    )]],
+                };
+                let silent = match settings::get(notification.chat_id) {
+                    Err(e) => {
+                        error!(
+                            "Failed to load settings of chat {chat_id}: {error}",
+                            chat_id = notification.chat_id,
+                            error = e
+                        );
+                        false
+                    }
+                    Ok(settings) => settings.silent,
+                };
+                if let Err(e) = bot
+                    .send_message(notification.chat_id, text)
+                    .parse_mode(ParseMode::HTML)
+                    .reply_markup(markup)
+                    .disable_notification(silent)
+                    .send()
+                    .await
+                {
+                    error!(
+                        "Failed to notify chat {chat_id} of crate `{crate_name}`: {error}",
+                        chat_id = notification.chat_id,
+                        crate_name = crate_name,
+                        error = e
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub async fn watch_crate(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/watch [crate-name] [yanked]</code>\n\
+        Get notified in this chat whenever a crate publishes a new version.\n\
+        \n\
+        <code>[crate-name]</code>: the name of a crate\n\
+        <code>[yanked]</code>: (optional) get notified of yanked versions as well";
+
+    if args.is_empty() || args.len() > 2 || args.get(1).is_some_and(|arg| arg != "yanked") {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        let crate_name = &args[0];
+        let versions = {
+            let result = crates::get_versions(crate_name).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get versions of crate `{crate_name}`: {error}",
+                        crate_name = crate_name,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(versions) = versions {
+            // Watch the crate by the name it is published with, e.g. `serde` for `Serde`
+            let crate_name = versions
+                .first()
+                .map_or(crate_name.as_str(), |version| version.crate_name.as_str());
+            info!("Watch {{ Chat = {}, Name = {} }}", cx.chat_id(), crate_name);
+
+            let yanked = args.len() == 2;
+            let result =
+                watch::subscribe(cx.chat_id(), crate_name, watch::Watch { yanked }, &versions)
+                    .await;
+            if let Err(e) = result {
+                error!(
+                    "Failed to save subscriptions of crate `{crate_name}`: {error}",
+                    crate_name = crate_name,
+                    error = e
+                );
+                return Ok(());
+            }
+            let text = format!(
+                "👀 Watching <b>{crate_name}</b>, this chat will be notified of new releases{yanked}",
+                crate_name = escape_html_entities(crate_name),
+                yanked = if yanked { " and yanks" } else { "" },
+            );
+            cx.reply_to(text).parse_mode(ParseMode::HTML).send().await?;
+        } else {
+            let not_found = format!(
+                "No crate `{crate_name}` has found",
+                crate_name = crate_name.replace('`', "\\`")
+            );
+            cx.reply_to(&not_found)
+                .parse_mode(ParseMode::MarkdownV2)
+                .send()
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn unwatch_crate(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/unwatch [crate-name]</code>\n\
+        Stop getting notified of the releases of a crate in this chat.\n\
+        \n\
+        <code>[crate-name]</code>: the name of a crate";
+
+    if args.is_empty() {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        let crate_name = &args[0];
+        let removed = match watch::unsubscribe(cx.chat_id(), crate_name).await {
+            Err(e) => {
+                error!(
+                    "Failed to save subscriptions of crate `{crate_name}`: {error}",
+                    crate_name = crate_name,
+                    error = e
+                );
+                return Ok(());
+            }
+            Ok(removed) => removed,
+        };
+        info!(
+            "Unwatch {{ Chat = {}, Name = {} }}",
+            cx.chat_id(),
+            crate_name
+        );
+
+        let text = if let Some(removed) = removed {
+            format!("Stopped watching <b>{}</b>", escape_html_entities(&removed))
+        } else {
+            format!(
+                "<b>{}</b> is not watched in this chat",
+                escape_html_entities(crate_name)
+            )
+        };
+        cx.reply_to(text).parse_mode(ParseMode::HTML).send().await?;
+    }
+    Ok(())
+}
+
+pub async fn list_subscriptions(cx: DispatcherHandlerCx<Message>) -> ResponseResult<()> {
+    let subscriptions = match watch::get_subscriptions(cx.chat_id()) {
+        Err(e) => {
+            error!(
+                "Failed to load subscriptions of chat {chat_id}: {error}",
+                chat_id = cx.chat_id(),
+                error = e
+            );
+            return Ok(());
+        }
+        Ok(subscriptions) => subscriptions,
+    };
+    info!("Watching {{ Chat = {} }}", cx.chat_id());
+
+    let text = if subscriptions.is_empty() {
+        "No crate is watched in this chat".to_string()
+    } else {
+        let entries: String = subscriptions
+            .iter()
+            .map(|(crate_name, watch)| {
+                let yanked = if watch.yanked {
+                    " <i>with yanks</i>"
+                } else {
+                    ""
+                };
+                format!("\n• <b>{}</b>{}", escape_html_entities(crate_name), yanked)
+            })
+            .collect();
+        format!("👀 Crates watched in this chat\n{}", entries)
+    };
+    cx.reply_to(text).parse_mode(ParseMode::HTML).send().await?;
+    Ok(())
+}
+
+pub async fn chat_settings(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/settings [name] [on|off]</code>\n\
+        Show or change the settings of this chat.\n\
+        \n\
+        <code>silent</code>: send release notifications without a sound";
+
+    let mut chat_settings = match settings::get(cx.chat_id()) {
+        Err(e) => {
+            error!(
+                "Failed to load settings of chat {chat_id}: {error}",
+                chat_id = cx.chat_id(),
+                error = e
+            );
+            return Ok(());
+        }
+        Ok(chat_settings) => chat_settings,
+    };
+    let value = match args.get(1).map(String::as_str) {
+        Some("on") => true,
+        Some("off") => false,
+        _ => {
+            if !args.is_empty() {
+                cx.reply_to(USAGE)
+                    .parse_mode(ParseMode::HTML)
+                    .send()
+                    .await?;
+                return Ok(());
+            }
+            info!("Settings {{ Chat = {} }}", cx.chat_id());
+
+            let text = format!(
+                "⚙️ Settings of this chat\n\n• <code>silent</code>: {}",
+                if chat_settings.silent { "on" } else { "off" }
+            );
+            cx.reply_to(text).parse_mode(ParseMode::HTML).send().await?;
+            return Ok(());
+        }
+    };
+    match args[0].as_str() {
+        "silent" if args.len() == 2 => chat_settings.silent = value,
+        _ => {
+            cx.reply_to(USAGE)
+                .parse_mode(ParseMode::HTML)
+                .send()
+                .await?;
+            return Ok(());
+        }
+    }
+    if let Err(e) = settings::set(cx.chat_id(), &chat_settings) {
+        error!(
+            "Failed to save settings of chat {chat_id}: {error}",
+            chat_id = cx.chat_id(),
+            error = e
+        );
+        return Ok(());
+    }
+    info!(
+        "Settings {{ Chat = {}, Name = {}, Value = {} }}",
+        cx.chat_id(),
+        args[0],
+        args[1]
+    );
+
+    let text = format!(
+        "<code>{}</code> is turned {} in this chat",
+        escape_html_entities(&args[0]),
+        args[1]
+    );
+    cx.reply_to(text).parse_mode(ParseMode::HTML).send().await?;
+    Ok(())
+}
+
+const REVERSE_DEPENDENCIES_PAGE_SIZE: usize = 10;
+
+pub async fn list_reverse_dependencies(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/rdeps [crate-name]</code>\n\
+        List the crates depending on a crate, sorted by downloads.\n\
+        \n\
+        <code>[crate-name]</code>: the name of a crate";
+
+    if args.is_empty() {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        cx.bot
+            .send_chat_action(cx.chat_id(), SendChatActionKind::Typing)
+            .send()
+            .await?;
+        let crate_name = &args[0];
+        let dependents = {
+            let result =
+                crates::get_reverse_dependencies(crate_name, 1, REVERSE_DEPENDENCIES_PAGE_SIZE)
+                    .await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get reverse dependencies of crate `{crate_name}`: {error}",
+                        crate_name = crate_name,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(dependents) = dependents {
+            info!("ReverseDeps {{ Name = {} }}", crate_name);
+
+            let (text, markup) = reverse_dependencies_page(crate_name, &dependents, 0);
+            let message = cx
+                .reply_to(text)
+                .parse_mode(ParseMode::HTML)
+                .reply_markup(markup)
+                .send()
+                .await?;
+            save_callback_session(
+                (message.chat_id(), message.id),
+                &CallbackSession::ReverseDependencies(crate_name.clone()),
+            );
+        } else {
+            let not_found = format!(
+                "No crate `{crate_name}` has found",
+                crate_name = crate_name.replace('`', "\\`")
+            );
+            cx.reply_to(&not_found)
+                .parse_mode(ParseMode::MarkdownV2)
+                .send()
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn list_reverse_dependencies_callback(
+    cx: DispatcherHandlerCx<CallbackQuery>,
+    crate_name: String,
+) -> ResponseResult<()> {
+    let message = cx.update.message.as_ref().unwrap();
+    let data = cx.update.data.as_ref().unwrap();
+
+    if let Ok(page) = data.parse::<usize>() {
+        let dependents = {
+            let result = crates::get_reverse_dependencies(
+                &crate_name,
+                page + 1,
+                REVERSE_DEPENDENCIES_PAGE_SIZE,
+            )
+            .await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get reverse dependencies of crate `{crate_name}`: {error}",
+                        crate_name = crate_name,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(dependents) = dependents {
+            info!("ReverseDeps {{ Name = {}, Page = {} }}", crate_name, page);
+
+            let (text, markup) = reverse_dependencies_page(&crate_name, &dependents, page);
+            cx.bot
+                .edit_message_text(
+                    ChatOrInlineMessage::Chat {
+                        chat_id: message.chat_id().into(),
+                        message_id: message.id,
+                    },
+                    text,
+                )
+                .parse_mode(ParseMode::HTML)
+                .reply_markup(markup)
+                .send()
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+fn reverse_dependencies_page(
+    crate_name: &str,
+    dependents: &crates::ReverseDependencies,
+    page: usize,
+) -> (String, InlineKeyboardMarkup) {
+    let page_count = (dependents.total.max(1) - 1) / REVERSE_DEPENDENCIES_PAGE_SIZE + 1;
+    let entries: String = dependents
+        .dependents
+        .iter()
+        .enumerate()
+        .map(|(i, dependent)| {
+            let kind = match (dependent.kind.as_str(), dependent.optional) {
+                ("normal", false) => String::new(),
+                ("normal", true) => " (optional)".to_string(),
+                (kind, false) => format!(" ({})", kind),
+                (kind, true) => format!(" ({}, optional)", kind),
+            };
+            format!(
+                "\n{index}. <b>{name}</b> <i>{version}</i>\n\
+                requires <code>{req}</code>{kind}, ⬇️{downloads} downloads\n",
+                index = page * REVERSE_DEPENDENCIES_PAGE_SIZE + i + 1,
+                name = dependent.name,
+                version = dependent.version,
+                req = escape_html_entities(&dependent.req),
+                kind = kind,
+                downloads = size_humanize(dependent.downloads),
+            )
+        })
+        .collect();
+    let text = if dependents.total == 0 {
+        format!("No crate depends on <b>{}</b>", crate_name)
+    } else {
+        format!(
+            "<b>{crate_name}</b> is depended on by {total} crates ({page}/{page_count})\n{entries}",
+            crate_name = crate_name,
+            total = dependents.total,
+            page = page + 1,
+            page_count = page_count,
+            entries = entries,
+        )
+    };
+    let markup = InlineKeyboardMarkup {
+        inline_keyboard: vec![page_navigation(page, page_count)],
+    };
+    (text, markup)
+}
+
+/// Buttons moving to the previous and the next page, carrying the page index.
+fn page_navigation(page: usize, page_count: usize) -> Vec<InlineKeyboardButton> {
+    let mut navigation = Vec::new();
+    if page > 0 {
+        navigation.push(InlineKeyboardButton::callback(
+            "◀️ Prev".into(),
+            (page - 1).to_string(),
+        ));
+    }
+    if page + 1 < page_count {
+        navigation.push(InlineKeyboardButton::callback(
+            "Next ▶️".into(),
+            (page + 1).to_string(),
+        ));
+    }
+    navigation
+}
+
+fn versions_page(
+    crate_name: &str,
+    versions: &[crates::CrateVersion],
+    page: usize,
+) -> (String, InlineKeyboardMarkup) {
+    const PAGE_SIZE: usize = 10;
+
+    let page_count = (versions.len().max(1) - 1) / PAGE_SIZE + 1;
+    let page = page.min(page_count - 1);
+    let entries: String = versions
+        .iter()
+        .skip(page * PAGE_SIZE)
+        .take(PAGE_SIZE)
+        .map(|version| {
+            let yanked = if version.yanked {
+                " ⚠️<b>Yanked</b>"
+            } else {
+                ""
+            };
+            let size = if let Some(size) = version.crate_size {
+                format!(" ({}B)", size_humanize(size))
+            } else {
+                String::new()
+            };
+            let license = if let Some(license) = &version.license {
+                format!("{} License", license)
+            } else {
+                "No License".into()
+            };
+            let rust_version = if let Some(rust_version) = &version.rust_version {
+                format!(", rust {}", rust_version)
+            } else {
+                String::new()
+            };
+            format!(
+                "\n<b>{version}</b>{size}{yanked}\n\
+                🕒 {created_at}, {license}{rust_version}\n",
+                version = version.version,
+                size = size,
+                yanked = yanked,
+                created_at = version.created_at.format("%Y-%m-%d"),
+                license = license,
+                rust_version = rust_version,
+            )
+        })
+        .collect();
+    let text = format!(
+        "<b>{crate_name}</b> versions ({page}/{page_count})\n{entries}",
+        crate_name = crate_name,
+        page = page + 1,
+        page_count = page_count,
+        entries = entries,
+    );
+    let markup = InlineKeyboardMarkup {
+        inline_keyboard: vec![page_navigation(page, page_count)],
+    };
+    (text, markup)
+}
+
+pub async fn find_crate(cx: DispatcherHandlerCx<Message>, args: Vec<String>) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/search [query]</code>\n\
+        Search crates on crates.io.\n\
+        \n\
+        <code>[query]</code>: keywords to search for";
+    const RESULT_COUNT: usize = 5;
+
+    if args.is_empty() {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        cx.bot
+            .send_chat_action(cx.chat_id(), SendChatActionKind::Typing)
+            .send()
+            .await?;
+        let query = args.join(" ");
+        let results = {
+            let result = crates::search_crates(&query, RESULT_COUNT).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to search crates with `{query}`: {error}",
+                        query = query,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if results.is_empty() {
+            let not_found = format!(
+                "No crate has found with `{query}`",
+                query = query.replace('`', "\\`")
+            );
+            cx.reply_to(&not_found)
+                .parse_mode(ParseMode::MarkdownV2)
+                .send()
+                .await?;
+            return Ok(());
+        }
+        info!("Search {{ Query = {} }}", query);
+
+        let entries: String = results
+            .iter()
+            .enumerate()
+            .map(|(i, result)| {
+                let description = if let Some(description) = &result.description {
+                    format!("\n{}", escape_html_entities(description.trim()))
+                } else {
+                    String::new()
+                };
+                format!(
+                    "\n\n{index}. <b>{name}</b> <i>{version}</i>{description}\n\
+                    ⬇️{recent} downloads recently ({total} total)",
+                    index = i + 1,
+                    name = result.name,
+                    version = result.newest_version,
+                    description = description,
+                    recent = size_humanize(result.recent_downloads.unwrap_or(0)),
+                    total = size_humanize(result.downloads),
+                )
+            })
+            .collect();
+        let text = format!(
+            "<b>Search results for</b> <i>{query}</i>{entries}",
+            query = escape_html_entities(&query),
+            entries = entries,
+        );
+        let markup = InlineKeyboardMarkup {
+            inline_keyboard: results
+                .iter()
+                .enumerate()
+                .map(|(i, result)| {
+                    vec![InlineKeyboardButton::callback(
+                        format!("{}. {}", i + 1, result.name),
+                        i.to_string(),
+                    )]
+                })
+                .collect(),
+        };
+        let message = cx
+            .reply_to(text)
+            .parse_mode(ParseMode::HTML)
+            .reply_markup(markup)
+            .send()
+            .await?;
+        let names = results.into_iter().map(|result| result.name).collect();
+        save_callback_session(
+            (message.chat_id(), message.id),
+            &CallbackSession::CrateList(names),
+        );
+    }
+    Ok(())
+}
+
+pub async fn crate_list_callback(
+    cx: DispatcherHandlerCx<CallbackQuery>,
+    names: Vec<String>,
+) -> ResponseResult<()> {
+    let message = cx.update.message.as_ref().unwrap();
+    let data = cx.update.data.as_ref().unwrap();
+
+    if let Some(crate_name) = data.parse::<usize>().ok().and_then(|i| names.get(i)) {
+        let information = {
+            let result = crates::get_information(crate_name, None).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get information of crate `{crate_name}`: {error}",
+                        crate_name = crate_name,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(information) = information {
+            info!("CrateInfo {{ Name = {}, From = List }}", crate_name);
+
+            let session = CallbackSession::Crate {
+                name: information.name.clone(),
+                version: information.version.clone(),
+            };
+            let advisories = advisory::get_affecting(&information.name, &information.version).await;
+            let (info_text, markup) = information_card(information, &advisories);
+            let message = cx
+                .bot
+                .send_message(message.chat_id(), info_text)
+                .parse_mode(ParseMode::HTML)
+                .reply_to_message_id(message.id)
+                .reply_markup(markup)
+                .send()
+                .await?;
+            save_callback_session((message.chat_id(), message.id), &session);
+        }
+    }
+    Ok(())
+}
+
+/// Documents shown in messages, whose sections are opened by the buttons.
+const DOCUMENTS: ExpiringTree<(i64, i32), search::CrateDocument> = ExpiringTree::new("documents");
+
+fn save_document(key: (i64, i32), document: &search::CrateDocument) {
+    if let Err(e) = DOCUMENTS.insert(&key, document) {
+        error!(
+            "Failed to save document of message {key:?}: {error}",
+            key = key,
+            error = e
+        );
+    }
+}
+
+pub async fn search_crate(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/docs [path]</code>\n\
+        Show online documentation with specified path in a crate.\n\
+        \n\
+        <code>[path]</code>: the path to the item, e.g. <code>std::option::Option::map</code>, <code>std::vec!</code>\n\
+        The crate may be pinned to a version, e.g. <code>tokio@0.2.13::runtime::Runtime</code>, \
+        or the standard library to a channel or a release, e.g. <code>std@nightly</code>, <code>std@1.41.0</code>";
+    const SUGGESTION_COUNT: usize = 5;
+
+    if args.is_empty() || !search::is_valid_pin(&args[0]) {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        cx.bot
+            .send_chat_action(cx.chat_id(), SendChatActionKind::Typing)
+            .send()
+            .await?;
+        let path = &args[0];
+        let document = {
+            let result = search::get_document(path).await;
+            match result {
+                Err(e) => {
+                    log::error!(
+                        "Failed to get information with path `{path}`: {error}",
+                        path = path,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(document) = document {
+            info!("Docs {{ Path = {} }}", path);
+
+            let (text, markup) = document_message(&document);
+            let message = cx
+                .reply_to(text)
+                .parse_mode(ParseMode::HTML)
+                .reply_markup(markup)
+                .send()
+                .await?;
+            save_document((message.chat_id(), message.id), &document);
+            save_callback_session(
+                (message.chat_id(), message.id),
+                &CallbackSession::Docs(DocumentSource::Docs(path.clone())),
+            );
+        } else {
+            let suggestions = match search::suggest_paths(path, SUGGESTION_COUNT).await {
+                Err(e) => {
+                    error!(
+                        "Failed to suggest paths for `{path}`: {error}",
+                        path = path,
+                        error = e
+                    );
+                    Vec::new()
+                }
+                Ok(suggestions) => suggestions,
+            };
+            reply_not_found(
+                &cx,
+                "item",
+                path,
+                suggestions,
+                CallbackSession::DocsSuggestions,
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the suggestions with the document of the chosen path.
+pub async fn docs_suggestion_callback(
+    cx: DispatcherHandlerCx<CallbackQuery>,
+    paths: Vec<String>,
+) -> ResponseResult<()> {
+    let message = cx.update.message.as_ref().unwrap();
+    let data = cx.update.data.as_ref().unwrap();
+
+    if let Some(path) = data.parse::<usize>().ok().and_then(|i| paths.get(i)) {
+        let document = {
+            let result = search::get_document(path).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get information with path `{path}`: {error}",
+                        path = path,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(document) = document {
+            info!("Docs {{ Path = {}, From = Suggestions }}", path);
+
+            let (text, markup) = document_message(&document);
+            cx.bot
+                .edit_message_text(
+                    ChatOrInlineMessage::Chat {
+                        chat_id: message.chat_id().into(),
+                        message_id: message.id,
+                    },
+                    text,
+                )
+                .parse_mode(ParseMode::HTML)
+                .reply_markup(markup)
+                .send()
+                .await?;
+            save_document((message.chat_id(), message.id), &document);
+            save_callback_session(
+                (message.chat_id(), message.id),
+                &CallbackSession::Docs(DocumentSource::Docs(path.clone())),
+            );
+        }
+    }
+    Ok(())
+}
+
+pub async fn lookup_lint(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/lint [name]</code>\n\
+        Show the documentation of a clippy or rustc lint.\n\
+        \n\
+        <code>[name]</code>: the name of the lint, e.g. <code>needless_borrow</code>, \
+        <code>clippy::needless_borrow</code>, <code>unused_must_use</code>";
+    const SUGGESTION_COUNT: usize = 5;
+
+    if args.is_empty() {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        cx.bot
+            .send_chat_action(cx.chat_id(), SendChatActionKind::Typing)
+            .send()
+            .await?;
+        let name = &args[0];
+        let document = {
+            let result = lints::get_lint(name).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get lint with name `{name}`: {error}",
+                        name = name,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(document) = document {
+            info!("Lint {{ Name = {} }}", name);
+
+            let (text, markup) = document_message(&document);
+            let message = cx
+                .reply_to(text)
+                .parse_mode(ParseMode::HTML)
+                .reply_markup(markup)
+                .send()
+                .await?;
+            save_document((message.chat_id(), message.id), &document);
+            save_callback_session(
+                (message.chat_id(), message.id),
+                &CallbackSession::Docs(DocumentSource::Lint(name.clone())),
+            );
+        } else {
+            let suggestions = match lints::suggest_lints(name, SUGGESTION_COUNT).await {
+                Err(e) => {
+                    error!(
+                        "Failed to suggest lints for `{name}`: {error}",
+                        name = name,
+                        error = e
+                    );
+                    Vec::new()
+                }
+                Ok(suggestions) => suggestions,
+            };
+            reply_not_found(
+                &cx,
+                "lint",
+                name,
+                suggestions,
+                CallbackSession::LintSuggestions,
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the suggestions with the document of the chosen lint.
+pub async fn lint_suggestion_callback(
+    cx: DispatcherHandlerCx<CallbackQuery>,
+    names: Vec<String>,
+) -> ResponseResult<()> {
+    let message = cx.update.message.as_ref().unwrap();
+    let data = cx.update.data.as_ref().unwrap();
+
+    if let Some(name) = data.parse::<usize>().ok().and_then(|i| names.get(i)) {
+        let document = {
+            let result = lints::get_lint(name).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get lint with name `{name}`: {error}",
+                        name = name,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(document) = document {
+            info!("Lint {{ Name = {}, From = Suggestions }}", name);
+
+            let (text, markup) = document_message(&document);
+            cx.bot
+                .edit_message_text(
+                    ChatOrInlineMessage::Chat {
+                        chat_id: message.chat_id().into(),
+                        message_id: message.id,
+                    },
+                    text,
+                )
+                .parse_mode(ParseMode::HTML)
+                .reply_markup(markup)
+                .send()
+                .await?;
+            save_document((message.chat_id(), message.id), &document);
+            save_callback_session(
+                (message.chat_id(), message.id),
+                &CallbackSession::Docs(DocumentSource::Lint(name.clone())),
+            );
+        }
+    }
+    Ok(())
+}
+
+pub async fn search_book(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+    book: Book,
+) -> ResponseResult<()> {
+    const RESULT_COUNT: usize = 5;
+
+    if args.is_empty() {
+        let usage = format!(
+            "<code>/{command} [query]</code>\n\
+            Search {title}.\n\
+            \n\
+            <code>[query]</code>: words to find in a chapter, e.g. <code>lifetime elision</code>",
+            command = book.command(),
+            title = book.title()
+        );
+        cx.reply_to(usage)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        cx.bot
+            .send_chat_action(cx.chat_id(), SendChatActionKind::Typing)
+            .send()
+            .await?;
+        let query = args.join(" ");
+        let results = {
+            let result = books::search(book, &query, RESULT_COUNT).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to search {book} with `{query}`: {error}",
+                        book = book.title(),
+                        query = query,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        info!("Book {{ Book = {}, Query = {} }}", book.command(), query);
+        if results.is_empty() {
+            let not_found = format!(
+                "No chapter of {title} matches `{query}`",
+                title = book.title(),
+                query = query.replace('`', "\\`")
+            );
+            cx.reply_to(&not_found)
+                .parse_mode(ParseMode::MarkdownV2)
+                .send()
+                .await?;
+        } else {
+            let list: Vec<String> = results
+                .iter()
+                .enumerate()
+                .map(|(i, result)| {
+                    format!(
+                        "{}. <a href=\"{}\">{}</a>\n<i>{}</i>",
+                        i + 1,
+                        result.url.replace('"', "&quot;"),
+                        escape_html_entities(&result.breadcrumbs),
+                        escape_html_entities(&result.snippet)
+                    )
+                })
+                .collect();
+            let text = format!(
+                "📖 <b>{title}</b> <i>{query}</i>\n\n{list}",
+                title = book.title(),
+                query = escape_html_entities(&query),
+                list = list.join("\n\n")
+            );
+            let markup = InlineKeyboardMarkup {
+                inline_keyboard: results
+                    .iter()
+                    .enumerate()
+                    .map(|(i, result)| {
+                        vec![InlineKeyboardButton::callback(
+                            format!("{}. {}", i + 1, result.breadcrumbs),
+                            i.to_string(),
+                        )]
+                    })
+                    .collect(),
+            };
+            let message = cx
+                .reply_to(text)
+                .parse_mode(ParseMode::HTML)
+                .disable_web_page_preview(true)
+                .reply_markup(markup)
+                .send()
+                .await?;
+            save_callback_session(
+                (message.chat_id(), message.id),
+                &CallbackSession::BookChapters(
+                    results.into_iter().map(|result| result.url).collect(),
+                ),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Sends the chosen chapter of the search results, with a button per section.
+pub async fn book_chapter_callback(
+    cx: DispatcherHandlerCx<CallbackQuery>,
+    urls: Vec<String>,
+) -> ResponseResult<()> {
+    let message = cx.update.message.as_ref().unwrap();
+    let data = cx.update.data.as_ref().unwrap();
+
+    if let Some(url) = data.parse::<usize>().ok().and_then(|i| urls.get(i)) {
+        let document = {
+            let result = books::get_chapter(url).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to get chapter `{url}`: {error}",
+                        url = url,
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(result) => result,
+            }
+        };
+        if let Some(document) = document {
+            info!("Book {{ Url = {}, From = Results }}", url);
+
+            let (text, markup) = document_message(&document);
+            let sent = cx
+                .bot
+                .send_message(message.chat_id(), text)
+                .parse_mode(ParseMode::HTML)
+                .reply_to_message_id(message.id)
+                .reply_markup(markup)
+                .send()
+                .await?;
+            save_document((sent.chat_id(), sent.id), &document);
+            save_callback_session(
+                (sent.chat_id(), sent.id),
+                &CallbackSession::Docs(DocumentSource::BookChapter(url.clone())),
+            );
+        }
+    }
+    Ok(())
+}
+
+pub async fn show_feature(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/feature [name]</code>\n\
+        Show a feature gate with its Unstable Book entry, its tracking issue and whether it is stabilized.\n\
+        \n\
+        <code>[name]</code>: the name of the feature, e.g. <code>never_type</code>";
+
+    if args.is_empty() {
+        cx.reply_to(USAGE)
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    } else {
+        cx.bot
+            .send_chat_action(cx.chat_id(), SendChatActionKind::Typing)
+            .send()
+            .await?;
+        send_feature(&cx.bot, &cx.update, &args[0]).await?;
+    }
+    Ok(())
+}
+
+/// Replies to `message` with the document of a feature gate.
+async fn send_feature(bot: &Arc<Bot>, message: &Message, name: &str) -> ResponseResult<()> {
+    let document = {
+        let result = unstable::get_feature(name).await;
+        match result {
+            Err(e) => {
+                error!(
+                    "Failed to get feature `{name}`: {error}",
+                    name = name,
+                    error = e
+                );
+                return Ok(());
+            }
+            Ok(result) => result,
+        }
+    };
+    if let Some(document) = document {
+        info!("Feature {{ Name = {} }}", name);
+
+        let (text, markup) = document_message(&document);
+        let sent = bot
+            .send_message(message.chat_id(), text)
+            .parse_mode(ParseMode::HTML)
+            .reply_to_message_id(message.id)
+            .reply_markup(markup)
+            .send()
+            .await?;
+        save_document((sent.chat_id(), sent.id), &document);
+        save_callback_session(
+            (sent.chat_id(), sent.id),
+            &CallbackSession::Docs(DocumentSource::Feature(name.to_string())),
+        );
+    } else {
+        bot.send_message(message.chat_id(), not_found_text("feature", name))
+            .parse_mode(ParseMode::HTML)
+            .reply_to_message_id(message.id)
+            .send()
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn rust_releases(
+    cx: DispatcherHandlerCx<Message>,
+    args: Vec<String>,
+) -> ResponseResult<()> {
+    const USAGE: &str = "<code>/rust [version]</code>\n\
+        Show the highlights of a Rust release.\n\
+        \n\
+        <code>[version]</code>: a version of Rust, e.g. <code>1.41</code>, <code>1.41.1</code>";
+
+    let today = releases::today();
+    let schedule = releases::schedule(today);
+    if args.is_empty() {
+        info!("Rust {{ Stable = 1.{} }}", schedule.stable);
+
+        let next_release = releases::release_date(schedule.beta);
+        let text = format!(
+            "🦀 <b>Rust</b> release channels\n\
+            \n\
+            Stable <a href=\"https://doc.rust-lang.org/stable/std/\">1.{stable}</a> released on {stable_date}\n\
+            Beta <a href=\"https://doc.rust-lang.org/beta/std/\">1.{beta}</a> to be released on {beta_date}\n\
+            Nightly <a href=\"https://doc.rust-lang.org/nightly/std/\">1.{nightly}</a> to be released on {nightly_date}\n\
+            \n\
+            The next stable release is in <b>{days}</b> days, on {beta_date}.\n\
+            Send <code>/rust 1.{stable}</code> for the highlights of a release.",
+            stable = schedule.stable,
+            stable_date = releases::release_date(schedule.stable),
+            beta = schedule.beta,
+            beta_date = next_release,
+            nightly = schedule.nightly,
+            nightly_date = releases::release_date(schedule.nightly),
+            days = (next_release - today).num_days(),
+        );
+        cx.reply_to(text)
+            .parse_mode(ParseMode::HTML)
+            .disable_web_page_preview(true)
+            .send()
+            .await?;
+        return Ok(());
+    }
+
+    let (minor, patch) = match releases::parse_version(&args[0]) {
+        Some(version) => version,
+        None => {
+            cx.reply_to(USAGE)
+                .parse_mode(ParseMode::HTML)
+                .send()
+                .await?;
+            return Ok(());
+        }
+    };
+    cx.bot
+        .send_chat_action(cx.chat_id(), SendChatActionKind::Typing)
+        .send()
+        .await?;
+    let document = {
+        let result = releases::get_release_notes(&args[0]).await;
+        match result {
+            Err(e) => {
+                error!(
+                    "Failed to get release notes of `{version}`: {error}",
+                    version = args[0],
+                    error = e
+                );
+                return Ok(());
+            }
+            Ok(result) => result,
+        }
+    };
+    if let Some(document) = document {
+        info!("Rust {{ Version = {} }}", args[0]);
+
+        let (text, mut markup) = document_message(&document);
+        markup.inline_keyboard.push(vec![InlineKeyboardButton::url(
+            "📚 Standard library".into(),
+            format!("https://doc.rust-lang.org/1.{}.{}/std/", minor, patch),
+        )]);
+        let message = cx
+            .reply_to(text)
+            .parse_mode(ParseMode::HTML)
+            .reply_markup(markup)
+            .send()
+            .await?;
+        save_document((message.chat_id(), message.id), &document);
+        save_callback_session(
+            (message.chat_id(), message.id),
+            &CallbackSession::Docs(DocumentSource::ReleaseNotes(args[0].clone())),
+        );
+    } else if minor > schedule.stable && minor <= schedule.nightly + 1 && patch == 0 {
+        // Only the releases already on the train are dated, which also keeps the date in range
+        let text = format!(
+            "🦀 Rust <b>1.{minor}.0</b> is to be released on {date}",
+            minor = minor,
+            date = releases::release_date(minor)
+        );
+        cx.reply_to(text).parse_mode(ParseMode::HTML).send().await?;
+    } else {
+        cx.reply_to(not_found_text("release", &args[0]))
+            .parse_mode(ParseMode::HTML)
+            .send()
+            .await?;
+    }
+    Ok(())
+}
+
+/// Renders the overview of a document with a button per section.
+fn document_message(document: &search::CrateDocument) -> (String, InlineKeyboardMarkup) {
+    let portability_text = if let Some(portability) = &document.portability_note {
+        format!("\n<i>{}</i>", portability)
+    } else {
+        String::new()
+    };
+
+    let stability_text = if let Some(stability) = &document.stability_note {
+        format!("\n<i>{}</i>", stability)
+    } else {
+        String::new()
+    };
+
+    let deprecated_text = if document.deprecated {
+        " <b>Deprecated</b>"
+    } else {
+        ""
+    };
+
+    let definition_text = if let Some(definition) = &document.definition {
+        format!("\n{}", definition)
+    } else {
+        String::new()
+    };
+
+    let text = format!(
+        "{title}{deprecated}{portability}{stability}{definition}\n\
+        \n\
+        {description}",
+        title = document.title,
+        deprecated = deprecated_text,
+        portability = portability_text,
+        stability = stability_text,
+        definition = definition_text,
+        description = document.description,
+    );
+    let mut inline_keyboard: Vec<Vec<InlineKeyboardButton>> = document
+        .sections
+        .iter()
+        .enumerate()
+        .map(|(i, (heading, _))| {
+            vec![InlineKeyboardButton::callback(
+                heading.clone(),
+                i.to_string(),
+            )]
+        })
+        .collect();
+    if let Some(feature) = document
+        .stability_note
+        .as_deref()
+        .and_then(unstable::feature_of)
+    {
+        inline_keyboard.push(vec![InlineKeyboardButton::callback(
+            format!("🔬 {}", feature),
+            "feature".into(),
+        )]);
+    }
+    let markup = InlineKeyboardMarkup { inline_keyboard };
+    (text, markup)
+}
+
+pub async fn search_crate_callback(
+    cx: DispatcherHandlerCx<CallbackQuery>,
+    source: DocumentSource,
+) -> ResponseResult<()> {
+    let message = cx.update.message.as_ref().unwrap();
+    let data = cx.update.data.as_ref().unwrap();
+
+    let document = match DOCUMENTS.get(&(message.chat_id(), message.id)) {
+        Err(e) => {
+            error!(
+                "Failed to load document of message {key:?}: {error}",
+                key = (message.chat_id(), message.id),
+                error = e
+            );
+            return Ok(());
+        }
+        Ok(document) => document,
+    };
+    let document = match document {
+        Some(document) => document,
+        // The document has expired, fetch it again from where it came from
+        None => {
+            let result = refetch_document(&source).await;
+            match result {
+                Err(e) => {
+                    error!(
+                        "Failed to fetch again document of message {key:?}: {error}",
+                        key = (message.chat_id(), message.id),
+                        error = e
+                    );
+                    return Ok(());
+                }
+                Ok(Some(document)) => {
+                    save_document((message.chat_id(), message.id), &document);
+                    document
+                }
+                Ok(None) => return expired_callback(cx).await,
+            }
+        }
+    };
+    if data == "feature" {
+        if let Some(feature) = document
+            .stability_note
+            .as_deref()
+            .and_then(unstable::feature_of)
+        {
+            send_feature(&cx.bot, message, &feature).await?;
+        }
+        return Ok(());
+    }
+    if let Some((heading, article)) = data
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| document.sections.get(i))
+    {
+        info!("Docs {{ Title = {}, Data = {} }}", document.title, data);
+        let portability_text = if let Some(portability) = &document.portability_note {
+            format!("\n<i>{}</i>", portability)
+        } else {
+            String::new()
+        };
+
+        let stability_text = if let Some(stability) = &document.stability_note {
+            format!("\n<i>{}</i>", stability)
+        } else {
+            String::new()
+        };
+
+        let deprecated_text = if document.deprecated {
+            " <b>Deprecated</b>"
+        } else {
+            ""
+        };
+
+        let definition_text = if let Some(definition) = &document.definition {
+            format!("\n{}", definition)
+        } else {
+            String::new()
+        };
+
+        let text = format!(
+            "{title}{deprecated}{portability}{stability}{definition}\n\
+                \n\
+                <b>{heading}</b>\n\
+                {article}\n",
+            title = document.title,
+            deprecated = deprecated_text,
+            portability = portability_text,
+            stability = stability_text,
+            definition = definition_text,
+            heading = escape_html_entities(heading),
+            article = article_to_text(article),
+        );
+
+        cx.bot
+            .edit_message_text(
+                ChatOrInlineMessage::Chat {
+                    chat_id: message.chat_id().into(),
+                    message_id: message.id,
+                },
+                text,
+            )
+            .parse_mode(ParseMode::HTML)
+            .reply_markup(message.reply_markup().unwrap().clone())
+            .send()
+            .await?;
+    }
+    Ok(())
+}
+
+/// Tells that the buttons of a message no longer work, as its session has expired.
+pub async fn expired_callback(cx: DispatcherHandlerCx<CallbackQuery>) -> ResponseResult<()> {
+    cx.bot
+        .answer_callback_query(cx.update.id.clone())
+        .text("This message has expired, please send the command again.")
+        .send()
+        .await?;
+    Ok(())
+}
+
+/// Fetches again a document whose saved copy has expired.
+async fn refetch_document(
+    source: &DocumentSource,
+) -> crate::cache::FetchResult<Option<search::CrateDocument>> {
+    match source {
+        DocumentSource::Docs(path) => search::get_document(path).await,
+        DocumentSource::Readme { name, version } => readme::get_readme(name, version).await,
+        DocumentSource::Explain(code) => Ok(error_index::get_explanation(code).await),
+        DocumentSource::Lint(name) => lints::get_lint(name).await,
+        DocumentSource::BookChapter(url) => books::get_chapter(url).await,
+        DocumentSource::Feature(name) => unstable::get_feature(name).await,
+        DocumentSource::ReleaseNotes(version) => releases::get_release_notes(version).await,
+    }
+}
+
+/// Evicts the callback sessions and documents of messages which have expired periodically.
+pub async fn sweep_sessions(limits: crate::config::SessionConfig) {
+    const INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+    loop {
+        tokio::time::delay_for(INTERVAL).await;
+        let result = crate::util::CALLBACK_SESSIONS
+            .evict(limits.capacity, limits.max_age)
+            .and_then(|sessions| {
+                DOCUMENTS
+                    .evict(limits.capacity, limits.max_age)
+                    .map(|documents| (sessions, documents))
+            });
+        match result {
+            Err(e) => error!("Failed to evict expired sessions: {error}", error = e),
+            Ok((0, 0)) => {}
+            Ok((sessions, documents)) => info!(
+                "Evict {{ Sessions = {}, Documents = {} }}",
+                sessions, documents
+            ),
+        }
+    }
+}
+
+/// Answers `@bot [path]` with the document of an item, and `@bot [query]` with the cards of
+/// matching crates. Chosen results are sent as the cards of `/docs` and `/crate`.
+pub async fn inline_query(cx: DispatcherHandlerCx<InlineQuery>) -> ResponseResult<()> {
+    const CACHE_TIME: i32 = 5 * 60;
+
+    let query = cx.update.query.trim();
+    if query.is_empty() {
+        return Ok(());
+    }
+    let results = {
+        let result = if query.contains("::") {
+            inline_document(query).await
+        } else {
+            inline_crates(query).await
+        };
+        match result {
+            Err(e) => {
+                error!(
+                    "Failed to answer inline query `{query}`: {error}",
+                    query = query,
+                    error = e
+                );
+                return Ok(());
+            }
+            Ok(result) => result,
+        }
+    };
+    info!(
+        "Inline {{ Query = {}, Results = {} }}",
+        query,
+        results.len()
+    );
+
+    cx.bot
+        .answer_inline_query(cx.update.id.clone(), results)
+        .cache_time(CACHE_TIME)
+        .send()
+        .await?;
+    Ok(())
+}
+
+async fn inline_crates(query: &str) -> crate::cache::FetchResult<Vec<InlineQueryResult>> {
+    const RESULT_COUNT: usize = 5;
+
+    let found = crates::search_crates(query, RESULT_COUNT).await?;
+    let informations = futures::future::join_all(
+        found
+            .iter()
+            .map(|result| crates::get_information(&result.name, None)),
+    )
+    .await;
+    let mut results = Vec::new();
+    for (found, information) in found.iter().zip(informations) {
+        let information = match information {
+            Err(e) => {
+                error!(
+                    "Failed to get information of crate `{crate_name}`: {error}",
+                    crate_name = found.name,
+                    error = e
+                );
+                continue;
+            }
+            Ok(Some(information)) => information,
+            Ok(None) => continue,
+        };
+        let title = format!("{} {}", information.name, information.version);
+        let advisories = advisory::get_affecting(&information.name, &information.version).await;
+        let (text, markup) = information_card(information, &advisories);
+        results.push(inline_article(
+            &found.name,
+            title,
+            found.description.clone(),
+            text,
+            markup,
+        ));
+    }
+    Ok(results)
+}
+
+async fn inline_document(path: &str) -> crate::cache::FetchResult<Vec<InlineQueryResult>> {
+    let document = match search::get_document(path).await? {
+        Some(document) => document,
+        None => return Ok(Vec::new()),
+    };
+    let (text, markup) = document_message(&document);
+    Ok(vec![inline_article(
+        "docs",
+        path.to_string(),
+        None,
+        text,
+        markup,
+    )])
+}
+
+/// Makes an inline result sending `text` as HTML.
+/// Only URL buttons are kept, as messages sent inline have no session for the others.
+fn inline_article(
+    id: &str,
+    title: String,
+    description: Option<String>,
+    text: String,
+    markup: InlineKeyboardMarkup,
+) -> InlineQueryResult {
+    let inline_keyboard: Vec<Vec<InlineKeyboardButton>> = markup
+        .inline_keyboard
+        .into_iter()
+        .map(|line| {
+            line.into_iter()
+                .filter(|button| matches!(button.kind, InlineKeyboardButtonKind::Url(_)))
+                .collect::<Vec<_>>()
+        })
+        .filter(|line| !line.is_empty())
+        .collect();
+    let reply_markup = if inline_keyboard.is_empty() {
+        None
+    } else {
+        Some(InlineKeyboardMarkup { inline_keyboard })
+    };
+    InlineQueryResult::Article(InlineQueryResultArticle {
+        id: id.to_string(),
+        title,
+        input_message_content: InputMessageContent::Text {
+            message_text: text,
+            parse_mode: Some(ParseMode::HTML),
+            disable_web_page_preview: None,
+        },
+        reply_markup,
+        url: None,
+        hide_url: None,
+        description,
+        thumb_url: None,
+        thumb_width: None,
+        thumb_height: None,
+    })
+}
+
 fn article_to_text(item: &search::Article) -> String {
     match item {
         search::Article::Text(text) => text.clone(),
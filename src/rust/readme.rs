@@ -0,0 +1,165 @@
+use super::search::{node_text, parse_document_paragraph, Article, CrateDocument};
+use crate::cache::{self, FetchResult};
+use crate::util::{escape_html_entities, BUTTON_LIMIT, TEXT_LIMIT};
+use scraper::{ElementRef, Html};
+
+/// Fetches the readme rendered by crates.io for a version of a crate.
+/// The readme is split into sections at every `h1` and `h2` heading.
+pub async fn get_readme(crate_name: &str, version: &str) -> FetchResult<Option<CrateDocument>> {
+    let url = format!(
+        "https://static.crates.io/readmes/{name}/{name}-{version}.html",
+        name = crate_name,
+        version = version
+    );
+    let response = cache::get(&url).await?;
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+    Ok(Some(parse_readme(crate_name, version, &response.text())))
+}
+
+fn parse_readme(crate_name: &str, version: &str, html: &str) -> CrateDocument {
+    let html = Html::parse_fragment(html);
+
+    let mut blocks = Vec::new();
+    collect_blocks(html.root_element(), &mut blocks);
+
+    let mut description = String::new();
+    let mut sections = Vec::new();
+    let mut heading = None;
+    let mut buffer = Vec::new();
+    for block in blocks.into_iter().chain(std::iter::once(Block::End)) {
+        let next_heading = match block {
+            Block::Text(text) => {
+                buffer.push(text);
+                continue;
+            }
+            Block::Heading(text) => Some(text),
+            Block::End => None,
+        };
+        let article = join_blocks(&buffer);
+        buffer.clear();
+        match std::mem::replace(&mut heading, next_heading) {
+            // Text before the first heading is the description
+            None => description = article,
+            Some(heading) if !article.is_empty() => {
+                sections.push((heading, Article::Text(article)))
+            }
+            Some(_) => {}
+        }
+    }
+    sections.truncate(BUTTON_LIMIT);
+
+    CrateDocument {
+        title: format!(
+            "📄 <b>{}</b> <i>{}</i> README",
+            escape_html_entities(crate_name),
+            escape_html_entities(version)
+        ),
+        definition: None,
+        portability_note: None,
+        stability_note: None,
+        deprecated: false,
+        description,
+        sections,
+    }
+}
+
+enum Block {
+    /// A heading splitting the readme into sections.
+    Heading(String),
+    /// A block of Telegram HTML.
+    Text(String),
+    /// The end of the readme.
+    End,
+}
+
+fn join_blocks(blocks: &[String]) -> String {
+    let mut text = String::new();
+    for block in blocks {
+        if text.len() + block.len() > TEXT_LIMIT {
+            text.push_str("...");
+            break;
+        }
+        text.push_str(block);
+        text.push('\n');
+    }
+    text
+}
+
+/// Converts the block elements under `parent` into Telegram HTML, in document order.
+fn collect_blocks(parent: ElementRef, blocks: &mut Vec<Block>) {
+    for element in parent.children().filter_map(ElementRef::wrap) {
+        match element.value().name() {
+            "h1" | "h2" => {
+                // Headings may have only an image, e.g. a logo, which makes no label
+                let heading = node_text(element).trim().to_string();
+                if !heading.is_empty() {
+                    blocks.push(Block::Heading(heading));
+                }
+            }
+            "h3" | "h4" | "h5" | "h6" => blocks.push(Block::Text(format!(
+                "<b>{}</b>",
+                escape_html_entities(node_text(element).trim())
+            ))),
+            "p" | "pre" | "ul" | "ol" => {
+                if let Some(html) = parse_document_paragraph(element) {
+                    blocks.push(Block::Text(html));
+                }
+            }
+            "blockquote" => {
+                let mut quoted = Vec::new();
+                collect_blocks(element, &mut quoted);
+                for block in quoted {
+                    if let Block::Text(text) = block {
+                        blocks.push(Block::Text(format!("<i>{}</i>", text)));
+                    }
+                }
+            }
+            "table" | "img" | "picture" | "hr" | "script" | "style" => {}
+            _ => collect_blocks(element, blocks),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section_text(article: &Article) -> &str {
+        match article {
+            Article::Text(text) => text,
+            _ => panic!("a readme section must be text"),
+        }
+    }
+
+    #[test]
+    fn split_into_sections_at_headings() {
+        let document = parse_readme(
+            "demo",
+            "1.0.0",
+            r#"<h1><img src="logo.png"></h1>
+<p>A <strong>demo</strong> crate.</p>
+<h2>Usage</h2>
+<pre><code class="language-toml">demo = "1"</code></pre>
+<h3>Features</h3>
+<ul><li>fast</li><li>small</li></ul>
+<h2>Empty</h2>
+<h2>License</h2>
+<p>See <a href="LICENSE">LICENSE</a>.</p>"#,
+        );
+        // The heading with only a logo does not start a section
+        assert_eq!(document.description, "A <b>demo</b> crate.\n");
+        let headings: Vec<&str> = document.sections.iter().map(|(h, _)| h.as_str()).collect();
+        // Sections without any text are dropped
+        assert_eq!(headings, vec!["Usage", "License"]);
+        assert_eq!(
+            section_text(&document.sections[0].1),
+            "<pre><code class=\"language-toml\">demo = \"1\"</code></pre>\n\
+             <b>Features</b>\n\
+             • fast\n• small\n"
+        );
+        // Relative links lead nowhere from Telegram
+        assert_eq!(section_text(&document.sections[1].1), "See LICENSE.\n");
+    }
+}
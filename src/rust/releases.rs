@@ -0,0 +1,177 @@
+use super::markdown::{self, join_blocks, Block};
+use super::search::{Article, CrateDocument};
+use crate::cache::{self, FetchResult};
+use crate::util::{escape_html_entities, BUTTON_LIMIT};
+use chrono::{Duration, NaiveDate, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+const RELEASE_NOTES: &str = "https://raw.githubusercontent.com/rust-lang/rust/master/RELEASES.md";
+
+/// The minor versions on each channel, by the six-week release train.
+pub struct Schedule {
+    pub stable: i64,
+    pub beta: i64,
+    pub nightly: i64,
+}
+
+/// The date the release train is followed at, in UTC.
+pub fn today() -> NaiveDate {
+    Utc::now().date_naive()
+}
+
+/// The day the six-week release train is counted from.
+/// Only 1.0.0 itself was released a day later, on 2015-05-15.
+fn train_start() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2015, 5, 14).unwrap()
+}
+
+/// When `1.{minor}.0` is released, as a new stable is released every six weeks since 1.0.0.
+/// Panics if the date is out of range, so `minor` should be near the current one.
+pub fn release_date(minor: i64) -> NaiveDate {
+    train_start() + Duration::weeks(6 * minor)
+}
+
+/// Finds the minor versions on each channel at `date`.
+pub fn schedule(date: NaiveDate) -> Schedule {
+    let stable = (date - train_start()).num_weeks() / 6;
+    Schedule {
+        stable,
+        beta: stable + 1,
+        nightly: stable + 2,
+    }
+}
+
+/// Reads the minor and the patch of a version of Rust 1, e.g. `1.41` or `1.41.1`.
+pub fn parse_version(version: &str) -> Option<(i64, i64)> {
+    let version = version.trim().trim_start_matches('v');
+    let mut parts = version.split('.');
+    let major: i64 = parts.next()?.parse().ok()?;
+    let minor: i64 = parts.next()?.parse().ok()?;
+    let patch: i64 = match parts.next() {
+        Some(patch) => patch.parse().ok()?,
+        None => 0,
+    };
+    if major != 1 || parts.next().is_some() {
+        return None;
+    }
+    Some((minor, patch))
+}
+
+/// Fetches the release notes of a version, with a section per heading,
+/// e.g. Language, Compiler, Libraries.
+/// Returns `None` if the version has no release notes yet.
+pub async fn get_release_notes(version: &str) -> FetchResult<Option<CrateDocument>> {
+    lazy_static! {
+        // e.g. Version 1.41.0 (2020-01-30)
+        //      ==========================
+        static ref VERSION_HEADING: Regex =
+            Regex::new(r"(?m)^Version (\d+\.\d+\.\d+) \(([^)]*)\)[ \t]*\r?\n=+[ \t]*$").unwrap();
+    }
+
+    let (minor, patch) = match parse_version(version) {
+        Some(version) => version,
+        None => return Ok(None),
+    };
+    let version = format!("1.{}.{}", minor, patch);
+    let response = cache::get(RELEASE_NOTES).await?;
+    if !response.status().is_success() {
+        return Err(format!("release notes responded with {}", response.status()).into());
+    }
+    let notes = response.text();
+
+    let headings: Vec<_> = VERSION_HEADING.captures_iter(&notes).collect();
+    let position = match headings.iter().position(|heading| heading[1] == version) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+    let heading = &headings[position];
+    let start = heading.get(0).unwrap().end();
+    let end = headings
+        .get(position + 1)
+        .map(|next| next.get(0).unwrap().start())
+        .unwrap_or_else(|| notes.len());
+    let date = heading[2].to_string();
+
+    let mut description = Vec::new();
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+    for block in markdown::blocks(&notes[start..end]) {
+        let html = match block {
+            Block::Heading(heading) => {
+                sections.push((heading, Vec::new()));
+                continue;
+            }
+            Block::Code { html, .. } => html,
+            Block::Text(html) => html,
+        };
+        // Cut long lists between their items rather than at their end
+        let paragraphs = match sections.last_mut() {
+            Some((_, paragraphs)) => paragraphs,
+            None => &mut description,
+        };
+        for (i, item) in html.split("\n• ").enumerate() {
+            if i == 0 {
+                paragraphs.push(item.to_string());
+            } else {
+                paragraphs.push(format!("• {}", item));
+            }
+        }
+    }
+    sections.retain(|(_, paragraphs)| !paragraphs.is_empty());
+    sections.truncate(BUTTON_LIMIT);
+    // Most releases have nothing before their first heading, so the first section is shown
+    let description = if description.is_empty() {
+        sections
+            .first()
+            .map(|(heading, paragraphs)| {
+                format!(
+                    "<b>{}</b>\n{}",
+                    escape_html_entities(heading),
+                    join_blocks(paragraphs)
+                )
+            })
+            .unwrap_or_default()
+    } else {
+        join_blocks(&description)
+    };
+
+    Ok(Some(CrateDocument {
+        title: format!("🦀 Rust <b>{}</b>", version),
+        definition: Some(format!("Released on {}", escape_html_entities(&date))),
+        portability_note: None,
+        stability_note: None,
+        deprecated: false,
+        description,
+        sections: sections
+            .into_iter()
+            .map(|(heading, paragraphs)| (heading, Article::Text(join_blocks(&paragraphs))))
+            .collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_versions_of_rust_1() {
+        assert_eq!(parse_version("1.41"), Some((41, 0)));
+        assert_eq!(parse_version(" v1.41.1 "), Some((41, 1)));
+        assert_eq!(parse_version("2.0"), None);
+        assert_eq!(parse_version("1"), None);
+        assert_eq!(parse_version("1.x"), None);
+        assert_eq!(parse_version("1.41.1.0"), None);
+    }
+
+    #[test]
+    fn follow_the_release_train() {
+        let release = NaiveDate::from_ymd_opt(2020, 1, 30).unwrap();
+        assert_eq!(release_date(41), release);
+        let on_release = schedule(release);
+        assert_eq!(
+            (on_release.stable, on_release.beta, on_release.nightly),
+            (41, 42, 43)
+        );
+        assert_eq!(schedule(release.pred_opt().unwrap()).stable, 40);
+    }
+}
@@ -1,8 +1,21 @@
-use crate::util::WEB_CLIENT;
+use super::search_index::get_index;
+use crate::cache::{self, Cache, FetchResult};
+use crate::util::split_version;
 use lazy_static::lazy_static;
-use reqwest::{header, StatusCode};
+use reqwest::StatusCode;
 use scraper::{ElementRef, Html, Selector};
 use selectors::attr::CaseSensitivity;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+lazy_static! {
+    /// Documents by the path of the item.
+    static ref DOCUMENT_CACHE: Cache<String, CrateDocument> =
+        Cache::new("documents", 100, Duration::from_secs(60 * 60));
+    /// Where docs.rs redirects for the documentation of each crate, by the requested URL.
+    static ref DOCS_RS_CACHE: Cache<String, Option<String>> =
+        Cache::new("docs.rs", 200, Duration::from_secs(10 * 60));
+}
 
 struct CrateStructure<'a> {
     module: &'a [&'a str],
@@ -10,15 +23,76 @@ struct CrateStructure<'a> {
     structure_type: StructureType,
 }
 
+#[derive(Clone, Copy)]
 enum StructureType {
     Module,
     Function,
     Struct,
+    Enum,
+    Union,
     Trait,
-    Method,
-    TraitMethod,
+    TraitAlias,
+    Macro,
+    Constant,
+    Static,
+    TypeAlias,
+    Derive,
+    Attribute,
+    Primitive,
+    Keyword,
+    /// A method documented in the page of its parent, by the page prefix of the parent.
+    /// e.g. `enum` for `Option::map`
+    Method(&'static str),
+}
+
+impl StructureType {
+    /// The type of an item with its own page, by the item type named by rustdoc.
+    fn from_kind(kind: &str) -> Option<Self> {
+        let structure_type = match kind {
+            "mod" => StructureType::Module,
+            "fn" => StructureType::Function,
+            "struct" => StructureType::Struct,
+            "enum" => StructureType::Enum,
+            "union" => StructureType::Union,
+            "trait" => StructureType::Trait,
+            "traitalias" => StructureType::TraitAlias,
+            "macro" => StructureType::Macro,
+            "constant" => StructureType::Constant,
+            "static" => StructureType::Static,
+            "type" => StructureType::TypeAlias,
+            "derive" => StructureType::Derive,
+            "attr" => StructureType::Attribute,
+            "primitive" => StructureType::Primitive,
+            "keyword" => StructureType::Keyword,
+            _ => return None,
+        };
+        Some(structure_type)
+    }
+
+    /// Prefix of the page of an item, e.g. `struct` for `struct.Vec.html`
+    fn page_prefix(self) -> &'static str {
+        match self {
+            StructureType::Module => "index",
+            StructureType::Function => "fn",
+            StructureType::Struct => "struct",
+            StructureType::Enum => "enum",
+            StructureType::Union => "union",
+            StructureType::Trait => "trait",
+            StructureType::TraitAlias => "traitalias",
+            StructureType::Macro => "macro",
+            StructureType::Constant => "constant",
+            StructureType::Static => "static",
+            StructureType::TypeAlias => "type",
+            StructureType::Derive => "derive",
+            StructureType::Attribute => "attr",
+            StructureType::Primitive => "primitive",
+            StructureType::Keyword => "keyword",
+            StructureType::Method(parent) => parent,
+        }
+    }
 }
 
+#[derive(Clone, Deserialize, Serialize)]
 pub struct CrateDocument {
     /// Title of this document.
     /// e.g. Struct ketera_bot::rust::search::CrateDocument
@@ -36,17 +110,17 @@ pub struct CrateDocument {
     /// It is a combined text of all paragraphs before the first heading.
     pub description: String,
     /// Additional sections of this document.
-    /// It consists of pairs of the heading and the article.
+    /// It consists of pairs of the heading in plain text, which labels a button, and the article.
     pub sections: Vec<(String, Article)>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum Article {
     Text(String),
     SubDocuments(Vec<SubDocument>),
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SubDocument {
     pub name: String,
     /// Portability of the content.
@@ -61,126 +135,129 @@ pub struct SubDocument {
 }
 
 impl<'a> CrateStructure<'a> {
-    async fn get_document(&self, crate_location: &str) -> reqwest::Result<Option<CrateDocument>> {
+    async fn get_document(&self, crate_location: &str) -> FetchResult<Option<CrateDocument>> {
+        Ok(self
+            .get_html(crate_location)
+            .await?
+            .and_then(|html| self.parse_document(&html)))
+    }
+
+    /// Reads the page of the item, either in the markup of current rustdoc
+    /// or in the older one still served for old versions on docs.rs.
+    fn parse_document(&self, html: &Html) -> Option<CrateDocument> {
+        // Each selector lists the older markup first, then the current one
         lazy_static! {
-            static ref TITLE_SELECTOR: Selector = Selector::parse(".fqn > .in-band").unwrap();
-            static ref PORTABILITY_SELECTOR: Selector =
-                Selector::parse("#main > .stability > .portability").unwrap();
-            static ref STABILITY_SELECTOR: Selector =
-                Selector::parse("#main > .stability > .unstable").unwrap();
-            static ref DEPRECATION_SELECTOR: Selector =
-                Selector::parse("#main > .stability > .deprecated").unwrap();
-            static ref MODULES_SELECTOR: Selector = Selector::parse("#modules + table tr").unwrap();
-            static ref STRUCTS_SELECTOR: Selector = Selector::parse("#structs + table tr").unwrap();
-            static ref TRAITS_SELECTOR: Selector = Selector::parse("#traits + table tr").unwrap();
-            static ref ENUMS_SELECTOR: Selector = Selector::parse("#enums + table tr").unwrap();
-            static ref MACROS_SELECTOR: Selector = Selector::parse("#macros + table tr").unwrap();
-            static ref FUNCTIONS_SELECTOR: Selector =
-                Selector::parse("#functions + table tr").unwrap();
-            static ref ATTRIBUTES_SELECTOR: Selector =
-                Selector::parse("#attributes + table tr").unwrap();
-            static ref CONSTS_SELECTOR: Selector = Selector::parse("#consts + table tr").unwrap();
-            static ref DEFINITION_SELECTOR: Selector =
-                Selector::parse("#main > .type_decl > pre").unwrap();
-            static ref DOCBLOCK_SELECTOR: Selector =
-                Selector::parse("#main > div.docblock:not(.type-decl)").unwrap();
-            static ref METHODS_SELECTOR: Selector =
-                Selector::parse("#impl + .impl-items h4 > code").unwrap();
-            static ref IMPLS_SELECTOR: Selector =
-                Selector::parse("#implementations-list .in-band").unwrap();
-            static ref REQUIRED_METHODS_SELECTOR: Selector =
-                Selector::parse("#required-methods + .methods .method > code").unwrap();
-            static ref PROVIDED_METHODS_SELECTOR: Selector =
-                Selector::parse("#provided-methods + .methods .method > code").unwrap();
-            static ref TRAIT_IMPLS_SELECTOR: Selector =
-                Selector::parse("#main > .impl .in-band").unwrap();
-            static ref TRAIT_IMPLORS_SELECTOR: Selector =
-                Selector::parse("#implementors-list .in-band").unwrap();
-            static ref METHOD_DEFINITION_SELECTOR: Selector = Selector::parse("code").unwrap();
-            static ref METHOD_PORTABILITY_SELECTOR: Selector =
-                Selector::parse(".portability").unwrap();
-            static ref METHOD_STABILITY_SELECTOR: Selector = Selector::parse(".unstable").unwrap();
-            static ref METHOD_DEPRECATION_SELECTOR: Selector =
-                Selector::parse(".deprecated").unwrap();
+            static ref TITLE_SELECTOR: Selector =
+                Selector::parse(".fqn > .in-band, .main-heading h1").unwrap();
+            static ref PORTABILITY_SELECTOR: Selector = Selector::parse(
+                "#main > .stability > .portability, #main-content > .item-info .portability"
+            )
+            .unwrap();
+            static ref STABILITY_SELECTOR: Selector = Selector::parse(
+                "#main > .stability > .unstable, #main-content > .item-info .unstable"
+            )
+            .unwrap();
+            static ref DEPRECATION_SELECTOR: Selector = Selector::parse(
+                "#main > .stability > .deprecated, #main-content > .item-info .deprecated"
+            )
+            .unwrap();
+            static ref MODULES_SELECTOR: Selector = module_items_selector("modules");
+            static ref STRUCTS_SELECTOR: Selector = module_items_selector("structs");
+            static ref TRAITS_SELECTOR: Selector = module_items_selector("traits");
+            static ref ENUMS_SELECTOR: Selector = module_items_selector("enums");
+            static ref MACROS_SELECTOR: Selector = module_items_selector("macros");
+            static ref FUNCTIONS_SELECTOR: Selector = module_items_selector("functions");
+            static ref ATTRIBUTES_SELECTOR: Selector = module_items_selector("attributes");
+            static ref CONSTS_SELECTOR: Selector = module_items_selector("constants");
+            static ref UNIONS_SELECTOR: Selector = module_items_selector("unions");
+            static ref TYPES_SELECTOR: Selector = module_items_selector("types");
+            static ref STATICS_SELECTOR: Selector = module_items_selector("statics");
+            static ref DERIVES_SELECTOR: Selector = module_items_selector("derives");
+            static ref TRAIT_ALIASES_SELECTOR: Selector = module_items_selector("trait-aliases");
+            static ref PRIMITIVES_SELECTOR: Selector = module_items_selector("primitives");
+            static ref KEYWORDS_SELECTOR: Selector = module_items_selector("keywords");
+            static ref DEFINITION_SELECTOR: Selector = Selector::parse(
+                "#main > .type_decl > pre, #main > .type-decl > pre, #main-content > pre.item-decl, #main-content > .item-decl > pre"
+            )
+            .unwrap();
+            static ref DOCBLOCK_SELECTOR: Selector = Selector::parse(
+                "#main > div.docblock:not(.type-decl), #main-content > details.top-doc > .docblock"
+            )
+            .unwrap();
+            static ref VARIANTS_SELECTOR: Selector = Selector::parse(
+                ".variant.small-section-header > code, section.variant > .code-header"
+            )
+            .unwrap();
+            static ref METHODS_SELECTOR: Selector = Selector::parse(
+                "#impl + .impl-items h4 > code, #implementations-list .impl-items .method > .code-header"
+            )
+            .unwrap();
+            static ref IMPLS_SELECTOR: Selector = Selector::parse(
+                "#implementations-list .in-band, #trait-implementations-list .impl > .code-header"
+            )
+            .unwrap();
+            static ref REQUIRED_METHODS_SELECTOR: Selector = Selector::parse(
+                "#required-methods + .methods .method > code, #required-methods + .methods .method > .code-header"
+            )
+            .unwrap();
+            static ref PROVIDED_METHODS_SELECTOR: Selector = Selector::parse(
+                "#provided-methods + .methods .method > code, #provided-methods + .methods .method > .code-header"
+            )
+            .unwrap();
+            static ref TRAIT_IMPLS_SELECTOR: Selector = Selector::parse(
+                "#main > .impl .in-band, #main-content > details > summary > .impl > .code-header"
+            )
+            .unwrap();
+            static ref TRAIT_IMPLORS_SELECTOR: Selector = Selector::parse(
+                "#implementors-list .in-band, #implementors-list .impl > .code-header"
+            )
+            .unwrap();
+            static ref METHOD_DEFINITION_SELECTOR: Selector =
+                Selector::parse("code, .code-header").unwrap();
         }
 
-        let html = match self.get_html(crate_location).await? {
-            Some(html) => html,
-            None => return Ok(None),
+        let title = html.select(&TITLE_SELECTOR).next()?;
+        let (definition, notes, docblock) = match self.structure_type {
+            StructureType::Method(_) => {
+                let selector_text = format!(
+                    "#tymethod\\.{method}, #method\\.{method}",
+                    method = self.name
+                );
+                let selector = Selector::parse(&selector_text).ok()?;
+                let definition_wrapper = html.select(&selector).next()?;
+                let definition = definition_wrapper
+                    .select(&METHOD_DEFINITION_SELECTOR)
+                    .next()
+                    .map(code_node_text);
+                let notes = item_notes(definition_wrapper);
+                let docblock = following_elements(definition_wrapper)
+                    .find(|element| element.value().has_class("docblock", CASE_SENSITIVE));
+                (definition, notes, docblock)
+            }
+            _ => {
+                let definition = html.select(&DEFINITION_SELECTOR).next().map(code_node_text);
+                let notes = ItemNotes {
+                    portability: html.select(&PORTABILITY_SELECTOR).next().map(node_text),
+                    stability: html.select(&STABILITY_SELECTOR).next().map(node_text),
+                    deprecated: html.select(&DEPRECATION_SELECTOR).next().is_some(),
+                };
+                let docblock = html.select(&DOCBLOCK_SELECTOR).next();
+                (definition, notes, docblock)
+            }
         };
 
-        let title = html.select(&TITLE_SELECTOR).next().unwrap();
-        let (definition, portability_note, stability_note, deprecated, docblock) =
-            match self.structure_type {
-                StructureType::Method | StructureType::TraitMethod => {
-                    let mut portability = None;
-                    let mut stability = None;
-                    let mut deprecated = false;
-                    let mut docblock = None;
-
-                    let selector_text = format!(
-                        "#tymethod\\.{method}, #method\\.{method}",
-                        method = self.name
-                    );
-                    let selector = match Selector::parse(&selector_text) {
-                        Ok(selector) => selector,
-                        Err(_) => return Ok(None),
-                    };
-                    let definition_wrapper = html.select(&selector).next().unwrap();
-                    let definition = definition_wrapper
-                        .select(&METHOD_DEFINITION_SELECTOR)
-                        .next()
-                        .map(code_node_text);
-                    for sibling in definition_wrapper
-                        .next_siblings()
-                        .filter_map(ElementRef::wrap)
-                    {
-                        let element = sibling.value();
-                        if element.name() != "div" {
-                            break;
-                        }
-                        if element.has_class("stability", CaseSensitivity::CaseSensitive) {
-                            portability = sibling
-                                .select(&METHOD_PORTABILITY_SELECTOR)
-                                .next()
-                                .map(node_text);
-                            stability = sibling
-                                .select(&METHOD_STABILITY_SELECTOR)
-                                .next()
-                                .map(node_text);
-                            deprecated = sibling
-                                .select(&METHOD_DEPRECATION_SELECTOR)
-                                .next()
-                                .is_some();
-                        } else if element.has_class("docblock", CaseSensitivity::CaseSensitive) {
-                            docblock = Some(sibling);
-                        }
-                    }
-                    // Docblock must be present
-                    (
-                        definition,
-                        portability,
-                        stability,
-                        deprecated,
-                        docblock.unwrap(),
-                    )
-                }
-                _ => {
-                    let definition = html.select(&DEFINITION_SELECTOR).next().map(code_node_text);
-                    let portability = html.select(&PORTABILITY_SELECTOR).next().map(node_text);
-                    let stability = html.select(&STABILITY_SELECTOR).next().map(node_text);
-                    let deprecated = html.select(&DEPRECATION_SELECTOR).next().is_some();
-                    let docblock = html.select(&DOCBLOCK_SELECTOR).next().unwrap();
-                    (definition, portability, stability, deprecated, docblock)
-                }
-            };
-
         let mut sections = Vec::new();
         let mut buffer = Vec::new();
-        for doc_element in docblock.children().filter_map(ElementRef::wrap).rev() {
-            if doc_element.value().name() == "h1" {
+        // Items without documentation have no docblock
+        let doc_elements: Vec<_> = docblock
+            .into_iter()
+            .flat_map(|docblock| docblock.children().filter_map(ElementRef::wrap))
+            .collect();
+        for doc_element in doc_elements.into_iter().rev() {
+            // Older rustdoc makes the headings of a docblock `h1`, current rustdoc `h2`
+            if let "h1" | "h2" = doc_element.value().name() {
                 buffer.reverse();
-                sections.push((node_text(doc_element), Article::Text(buffer.join("\n"))));
+                sections.push((heading_text(doc_element), Article::Text(buffer.join("\n"))));
                 buffer.clear();
             } else if let Some(paragraph) = parse_document_paragraph(doc_element) {
                 buffer.push(paragraph);
@@ -205,7 +282,7 @@ impl<'a> CrateStructure<'a> {
                     ($name:literal, $selector:ident) => {
                         let subdocuments: Vec<SubDocument> = html
                             .select(&$selector)
-                            .map(parse_module_subdocument)
+                            .filter_map(parse_module_subdocument)
                             .collect();
                         if !subdocuments.is_empty() {
                             sections.push(($name.into(), Article::SubDocuments(subdocuments)));
@@ -220,8 +297,23 @@ impl<'a> CrateStructure<'a> {
                 add_module_subdocuments!("Functions", FUNCTIONS_SELECTOR);
                 add_module_subdocuments!("Attributes", ATTRIBUTES_SELECTOR);
                 add_module_subdocuments!("Constants", CONSTS_SELECTOR);
+                add_module_subdocuments!("Unions", UNIONS_SELECTOR);
+                add_module_subdocuments!("Type Definitions", TYPES_SELECTOR);
+                add_module_subdocuments!("Statics", STATICS_SELECTOR);
+                add_module_subdocuments!("Derive Macros", DERIVES_SELECTOR);
+                add_module_subdocuments!("Trait Aliases", TRAIT_ALIASES_SELECTOR);
+                add_module_subdocuments!("Primitive Types", PRIMITIVES_SELECTOR);
+                add_module_subdocuments!("Keywords", KEYWORDS_SELECTOR);
             }
-            StructureType::Struct => {
+            StructureType::Enum => {
+                add_subdocuments!("Variants", VARIANTS_SELECTOR);
+                add_subdocuments!("Methods", METHODS_SELECTOR);
+                add_subdocuments!("Trait Implementations", IMPLS_SELECTOR);
+            }
+            StructureType::Struct
+            | StructureType::Union
+            | StructureType::Primitive
+            | StructureType::TypeAlias => {
                 add_subdocuments!("Methods", METHODS_SELECTOR);
                 add_subdocuments!("Trait Implementations", IMPLS_SELECTOR);
             }
@@ -233,209 +325,231 @@ impl<'a> CrateStructure<'a> {
             }
             _ => {}
         }
-        Ok(Some(CrateDocument {
-            title: node_text(title),
+        Some(CrateDocument {
+            title: title_text(html, title),
             definition,
-            deprecated,
+            deprecated: notes.deprecated,
             description,
-            portability_note,
+            portability_note: notes.portability,
             sections,
-            stability_note,
-        }))
+            stability_note: notes.stability,
+        })
     }
 
-    async fn get_html(&self, crate_location: &str) -> reqwest::Result<Option<Html>> {
+    async fn get_html(&self, crate_location: &str) -> FetchResult<Option<Html>> {
         let mut url = crate_location.to_string();
         // without crate name
         let effective_module = &self.module[1..];
-        match self.structure_type {
-            StructureType::Module => {
-                // module/submodule/index.html
-                url.push_str(&effective_module.join("/"));
-                if !effective_module.is_empty() {
-                    url.push('/');
-                }
-                url.push_str("index.html");
-            }
-            StructureType::Function => {
-                // module/submodule/fn.foo.html
-                url.push_str(&effective_module.join("/"));
-                if !effective_module.is_empty() {
-                    url.push('/');
-                }
-                url.push_str("fn.");
-                url.push_str(self.name);
-                url.push_str(".html");
-            }
-            StructureType::Struct => {
-                // module/submodule/struct.foo.html
-                url.push_str(&effective_module.join("/"));
-                if !effective_module.is_empty() {
-                    url.push('/');
-                }
-                url.push_str("struct");
-                url.push_str(self.name);
-                url.push_str(".html");
-            }
-            StructureType::Trait => {
-                // module/submodule/trait.foo.html
-                url.push_str(&effective_module.join("/"));
-                if !effective_module.is_empty() {
-                    url.push('/');
-                }
-                url.push_str("/trait");
-                url.push_str(self.name);
-                url.push_str(".html");
-            }
-            StructureType::Method => {
-                // module/submodule/struct.foo.html#method.bar
-                let effective_module = &effective_module[..effective_module.len() - 1];
-                url.push_str(&effective_module.join("/"));
-                if !effective_module.is_empty() {
-                    url.push('/');
-                }
-                url.push_str("struct");
-                url.push_str(self.module[self.module.len() - 1]);
-                url.push_str(".html");
-            }
-            StructureType::TraitMethod => {
-                // module/submodule/trait.foo.html#tymethod.bar OR
-                // module/submodule/trait.foo.html#method.bar
-                let effective_module = &effective_module[..effective_module.len() - 1];
-                url.push_str(&effective_module.join("/"));
-                if !effective_module.is_empty() {
-                    url.push('/');
-                }
-                url.push_str("trait");
-                url.push_str(self.module[self.module.len() - 1]);
-                url.push_str(".html");
-            }
+        let (directory, file) = match self.structure_type {
+            // module/submodule/index.html
+            StructureType::Module => (effective_module, "index.html".to_string()),
+            // module/submodule/struct.foo.html#method.bar
+            StructureType::Method(parent) => (
+                &effective_module[..effective_module.len() - 1],
+                format!("{}.{}.html", parent, self.module[self.module.len() - 1]),
+            ),
+            // module/submodule/fn.foo.html
+            item => (
+                effective_module,
+                format!("{}.{}.html", item.page_prefix(), self.name),
+            ),
+        };
+        url.push_str(&directory.join("/"));
+        if !directory.is_empty() {
+            url.push('/');
         }
+        url.push_str(&file);
 
-        let response = WEB_CLIENT.get(&url).send().await?;
+        let response = cache::get(&url).await?;
         if !response.status().is_success() {
             Ok(None)
         } else {
-            Ok(Some(Html::parse_document(&response.text().await?)))
+            Ok(Some(Html::parse_document(&response.text())))
         }
     }
 }
 
-pub async fn get_document(path: &str) -> reqwest::Result<Option<CrateDocument>> {
-    use tokio::try_join;
+pub async fn get_document(path: &str) -> FetchResult<Option<CrateDocument>> {
+    if let Some(document) = DOCUMENT_CACHE.get(path) {
+        return Ok(Some(document));
+    }
+    let document = fetch_document(path).await?;
+    if let Some(document) = &document {
+        DOCUMENT_CACHE.insert(path.to_string(), document.clone());
+    }
+    Ok(document)
+}
+
+/// Suggests paths of items in the crate of `path` similar to it, the closest first.
+/// The version of the crate in `path` is kept in the suggestions.
+pub async fn suggest_paths(path: &str, count: usize) -> FetchResult<Vec<String>> {
+    let (path, version) = split_pinned_version(path);
+    let crate_name = path.split("::").next().unwrap_or_default();
+    let c = match get_document_root(crate_name, version).await? {
+        Some(c) => c,
+        None => return Ok(Vec::new()),
+    };
+    let suggestions = match get_index(&c).await? {
+        Some(index) => index.suggest(&path, count),
+        None => return Ok(Vec::new()),
+    };
+    Ok(match version {
+        Some(version) => suggestions
+            .into_iter()
+            .map(|suggestion| match suggestion.find("::") {
+                Some(end) => format!("{}@{}{}", &suggestion[..end], version, &suggestion[end..]),
+                None => format!("{}@{}", suggestion, version),
+            })
+            .collect(),
+        None => suggestions,
+    })
+}
+
+async fn fetch_document(path: &str) -> FetchResult<Option<CrateDocument>> {
+    let (path, version) = split_pinned_version(path);
     let tree: Vec<_> = path.split("::").collect();
     if tree.is_empty() {
         return Ok(None);
     }
-    let c = if let Some(c) = get_latest_document(tree[0]).await? {
+    let c = if let Some(c) = get_document_root(tree[0], version).await? {
         c
     } else {
         return Ok(None);
     };
-    let tree = &tree[..];
-    let result = if tree.len() == 1 {
-        CrateStructure {
-            module: tree,
+    if tree.len() == 1 {
+        return CrateStructure {
+            module: &tree,
             name: tree[0],
             structure_type: StructureType::Module,
         }
         .get_document(&c)
-        .await?
-    } else if tree.len() == 2 {
-        let module_candidate = CrateStructure {
-            module: tree,
-            name: tree[1],
-            structure_type: StructureType::Module,
-        };
-        let function_candidate = CrateStructure {
-            module: &tree[..1],
-            name: tree[1],
-            structure_type: StructureType::Function,
-        };
-        let struct_candidate = CrateStructure {
-            structure_type: StructureType::Struct,
-            ..function_candidate
-        };
-        let trait_candidate = CrateStructure {
-            structure_type: StructureType::Struct,
-            ..function_candidate
-        };
-        let (maybe_module, maybe_function, maybe_struct, maybe_trait) = try_join!(
-            module_candidate.get_document(&c),
-            function_candidate.get_document(&c),
-            struct_candidate.get_document(&c),
-            trait_candidate.get_document(&c)
-        )?;
-        match (maybe_module, maybe_function, maybe_struct, maybe_trait) {
-            (Some(found), _, _, _)
-            | (_, Some(found), _, _)
-            | (_, _, Some(found), _)
-            | (_, _, _, Some(found)) => Some(found),
-            _ => None,
+        .await;
+    }
+
+    let index = match get_index(&c).await? {
+        Some(index) => index,
+        // The index of the documentation cannot be read, so the page of the item is guessed
+        None => return guess_document(&c, &tree).await,
+    };
+    let item = match index.resolve(&path) {
+        Some(item) => item,
+        None => return Ok(None),
+    };
+    let mut module: Vec<&str> = item.path.split("::").collect();
+    let (name, structure_type) = match &item.parent {
+        None if item.kind == "mod" => {
+            module.push(&item.name);
+            (item.name.as_str(), StructureType::Module)
         }
-    } else {
-        let module_candidate = CrateStructure {
-            module: tree,
-            name: tree[tree.len() - 1],
-            structure_type: StructureType::Module,
-        };
-        let function_candidate = CrateStructure {
-            module: &tree[..tree.len() - 1],
-            name: tree[tree.len() - 1],
-            structure_type: StructureType::Function,
-        };
-        let struct_candidate = CrateStructure {
-            structure_type: StructureType::Struct,
-            ..function_candidate
-        };
-        let trait_candidate = CrateStructure {
-            structure_type: StructureType::Trait,
-            ..function_candidate
-        };
-        let method_candidate = CrateStructure {
-            structure_type: StructureType::Method,
-            ..function_candidate
-        };
-        let trait_method_candidate = CrateStructure {
-            structure_type: StructureType::TraitMethod,
-            ..function_candidate
-        };
-        let (
-            maybe_module,
-            maybe_function,
-            maybe_struct,
-            maybe_trait,
-            maybe_method,
-            maybe_trait_method,
-        ) = try_join!(
-            module_candidate.get_document(&c),
-            function_candidate.get_document(&c),
-            struct_candidate.get_document(&c),
-            trait_candidate.get_document(&c),
-            method_candidate.get_document(&c),
-            trait_method_candidate.get_document(&c)
-        )?;
-        match (
-            maybe_module,
-            maybe_function,
-            maybe_struct,
-            maybe_trait,
-            maybe_method,
-            maybe_trait_method,
-        ) {
-            (Some(found), _, _, _, _, _)
-            | (_, Some(found), _, _, _, _)
-            | (_, _, Some(found), _, _, _)
-            | (_, _, _, Some(found), _, _)
-            | (_, _, _, _, Some(found), _)
-            | (_, _, _, _, _, Some(found)) => Some(found),
-            _ => None,
+        None => match StructureType::from_kind(&item.kind) {
+            Some(structure_type) => (item.name.as_str(), structure_type),
+            None => return Ok(None),
+        },
+        Some((parent_kind, parent)) => {
+            let parent_type = match StructureType::from_kind(parent_kind) {
+                Some(parent_type) => parent_type,
+                None => return Ok(None),
+            };
+            if item.kind == "method" || item.kind == "tymethod" {
+                module.push(parent);
+                (
+                    item.name.as_str(),
+                    StructureType::Method(parent_type.page_prefix()),
+                )
+            } else {
+                // Variants, fields and associated items are shown in the document of the parent
+                (parent.as_str(), parent_type)
+            }
         }
     };
-    Ok(result)
+    CrateStructure {
+        module: &module,
+        name,
+        structure_type,
+    }
+    .get_document(&c)
+    .await
+}
+
+/// Looks up an item by trying the pages it may have, by the naming conventions of Rust.
+async fn guess_document(crate_location: &str, tree: &[&str]) -> FetchResult<Option<CrateDocument>> {
+    let last = tree[tree.len() - 1];
+    let candidates =
+        if let Some(name) = last.strip_suffix('!') {
+            // e.g. std::vec!
+            vec![CrateStructure {
+                module: &tree[..tree.len() - 1],
+                name,
+                structure_type: StructureType::Macro,
+            }]
+        } else {
+            let mut candidates: Vec<_> = item_types(last, tree.len() == 2)
+                .into_iter()
+                .map(|structure_type| CrateStructure {
+                    module: if let StructureType::Module = structure_type {
+                        tree
+                    } else {
+                        &tree[..tree.len() - 1]
+                    },
+                    name: last,
+                    structure_type,
+                })
+                .collect();
+            if tree.len() > 2 {
+                let parent = tree[tree.len() - 2];
+                candidates.extend(parent_types(parent, tree.len() == 3).into_iter().map(
+                    |parent| CrateStructure {
+                        module: &tree[..tree.len() - 1],
+                        name: last,
+                        structure_type: StructureType::Method(parent),
+                    },
+                ));
+            }
+            candidates
+        };
+    let found = futures::future::try_join_all(
+        candidates
+            .iter()
+            .map(|candidate| candidate.get_document(crate_location)),
+    )
+    .await?;
+    // Candidates are in the order of precedence
+    Ok(found.into_iter().flatten().next())
+}
+
+/// Types an item named `name` may be of by the naming conventions of Rust, in the order of precedence.
+/// Primitives and keywords are only documented in the root of a crate.
+fn item_types(name: &str, in_crate_root: bool) -> Vec<StructureType> {
+    use StructureType::*;
+    let uppercase = name.starts_with(|c: char| c.is_ascii_uppercase());
+    let screaming = name.len() > 1
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    if screaming {
+        vec![Constant, Static]
+    } else if uppercase {
+        vec![Struct, Enum, Trait, Union, TypeAlias, TraitAlias, Derive]
+    } else if in_crate_root {
+        vec![Module, Function, Macro, Attribute, Primitive, Keyword]
+    } else {
+        vec![Module, Function, Macro, Attribute]
+    }
 }
 
-fn node_text(item: ElementRef) -> String {
+/// Page prefixes of the types which may have a method, by the name of the type.
+/// Methods of primitives are only documented in the root of a crate.
+fn parent_types(name: &str, in_crate_root: bool) -> Vec<&'static str> {
+    if name.starts_with(|c: char| c.is_ascii_uppercase()) {
+        vec!["struct", "enum", "trait", "union", "type"]
+    } else if in_crate_root {
+        vec!["primitive"]
+    } else {
+        Vec::new()
+    }
+}
+
+pub(super) fn node_text(item: ElementRef) -> String {
     item.text().collect()
 }
 
@@ -453,27 +567,142 @@ fn code_node_text(code: ElementRef) -> String {
     )
 }
 
-fn parse_module_subdocument(item: ElementRef) -> SubDocument {
+const CASE_SENSITIVE: CaseSensitivity = CaseSensitivity::CaseSensitive;
+
+/// Selects the items listed under a heading of a module page, e.g. `structs`.
+/// Older rustdoc lists them in a table, then in a list, and current rustdoc in a description list.
+fn module_items_selector(id: &str) -> Selector {
+    Selector::parse(&format!(
+        "#{id} + table tr, #{id} + .item-table > li, #{id} + .item-table > dt",
+        id = id
+    ))
+    .unwrap()
+}
+
+/// Text of a heading without the buttons and the anchors current rustdoc adds to it.
+fn heading_text(heading: ElementRef) -> String {
+    heading
+        .descendants()
+        .filter(|node| {
+            !node
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .any(|element| {
+                    element.value().name() == "button"
+                        || element.value().has_class("doc-anchor", CASE_SENSITIVE)
+                        || element.value().has_class("anchor", CASE_SENSITIVE)
+                })
+        })
+        .filter_map(|node| node.value().as_text().map(|text| &**text))
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// The title of a page with the full path of the item, e.g. `Struct std::vec::Vec`.
+/// Current rustdoc puts the parent modules apart in breadcrumbs.
+fn title_text(html: &Html, title: ElementRef) -> String {
+    lazy_static! {
+        static ref BREADCRUMBS_SELECTOR: Selector =
+            Selector::parse(".main-heading .rustdoc-breadcrumbs").unwrap();
+    }
+    let text = heading_text(title);
+    match (
+        html.select(&BREADCRUMBS_SELECTOR).next(),
+        text.split_once(' '),
+    ) {
+        (Some(breadcrumbs), Some((kind, name))) => {
+            format!("{} {}::{}", kind, heading_text(breadcrumbs), name.trim())
+        }
+        _ => text,
+    }
+}
+
+/// Elements following an item of a page, up to the next item.
+/// Current rustdoc wraps an item in a `summary`, which the docblock follows.
+fn following_elements(item: ElementRef) -> impl Iterator<Item = ElementRef> {
+    let in_summary = item
+        .parent()
+        .and_then(ElementRef::wrap)
+        .filter(|parent| parent.value().name() == "summary");
+    item.next_siblings()
+        .filter_map(ElementRef::wrap)
+        .chain(
+            in_summary
+                .into_iter()
+                .flat_map(|summary| summary.next_siblings().filter_map(ElementRef::wrap)),
+        )
+        .take_while(|element| matches!(element.value().name(), "div" | "span"))
+}
+
+struct ItemNotes {
+    portability: Option<String>,
+    stability: Option<String>,
+    deprecated: bool,
+}
+
+/// Reads the notes following an item, in `.stability` for older rustdoc
+/// and in `.item-info` for current rustdoc.
+fn item_notes(item: ElementRef) -> ItemNotes {
+    lazy_static! {
+        static ref PORTABILITY_SELECTOR: Selector = Selector::parse(".portability").unwrap();
+        static ref STABILITY_SELECTOR: Selector = Selector::parse(".unstable").unwrap();
+        static ref DEPRECATION_SELECTOR: Selector = Selector::parse(".deprecated").unwrap();
+    }
+    let mut notes = ItemNotes {
+        portability: None,
+        stability: None,
+        deprecated: false,
+    };
+    for info in following_elements(item).take_while(|element| {
+        element.value().has_class("stability", CASE_SENSITIVE)
+            || element.value().has_class("item-info", CASE_SENSITIVE)
+    }) {
+        notes.portability = notes
+            .portability
+            .or_else(|| info.select(&PORTABILITY_SELECTOR).next().map(node_text));
+        notes.stability = notes
+            .stability
+            .or_else(|| info.select(&STABILITY_SELECTOR).next().map(node_text));
+        notes.deprecated |= info.select(&DEPRECATION_SELECTOR).next().is_some();
+    }
+    notes
+}
+
+fn parse_module_subdocument(item: ElementRef) -> Option<SubDocument> {
     lazy_static! {
-        static ref NAME_SELECTOR: Selector = Selector::parse("td").unwrap();
+        static ref NAME_SELECTOR: Selector = Selector::parse("a").unwrap();
         static ref DEPRECATED_SELECTOR: Selector = Selector::parse(".deprecated").unwrap();
         static ref PORTABILITY_SELECTOR: Selector = Selector::parse(".portability").unwrap();
         static ref STABILITY_SELECTOR: Selector = Selector::parse(".unstable").unwrap();
-        static ref SUMMARY_SELECTOR: Selector = Selector::parse(".docblock-short > p").unwrap();
+        static ref SUMMARY_SELECTOR: Selector = Selector::parse(".docblock-short").unwrap();
+        static ref PARAGRAPH_SELECTOR: Selector = Selector::parse("p").unwrap();
     }
 
-    let name = node_text(item.select(&NAME_SELECTOR).next().unwrap());
+    let name = node_text(item.select(&NAME_SELECTOR).next()?);
     let is_deprecated = item.select(&DEPRECATED_SELECTOR).next().is_some();
     let portability = item.select(&PORTABILITY_SELECTOR).next().map(node_text);
     let stability = item.select(&STABILITY_SELECTOR).next().map(node_text);
-    let summary = item.select(&SUMMARY_SELECTOR).next().unwrap().inner_html();
-    SubDocument {
+    // Current rustdoc puts the summary in the `dd` following the `dt` of the name
+    let summary = if item.value().name() == "dt" {
+        item.next_siblings()
+            .filter_map(ElementRef::wrap)
+            .next()
+            .filter(|element| element.value().name() == "dd")
+    } else {
+        item.select(&SUMMARY_SELECTOR).next()
+    };
+    let summary = summary.map(|summary| match summary.select(&PARAGRAPH_SELECTOR).next() {
+        Some(paragraph) => paragraph.inner_html(),
+        None => summary.inner_html(),
+    });
+    Some(SubDocument {
         name,
         portability_note: portability,
         stability_note: stability,
         deprecated: is_deprecated,
-        summary: Some(summary),
-    }
+        summary,
+    })
 }
 
 fn parse_subdocument(item: ElementRef) -> SubDocument {
@@ -484,74 +713,344 @@ fn parse_subdocument(item: ElementRef) -> SubDocument {
     }
 
     let name = code_node_text(item);
-    let deprecated = item.select(&DEPRECATED_SELECTOR).next().is_some();
-    let portability = item.select(&PORTABILITY_SELECTOR).next().map(node_text);
-    let stability = item.select(&STABILITY_SELECTOR).next().map(node_text);
+    // The notes follow the heading or the `section` holding the item,
+    // though some older pages put them inside the item itself
+    let notes = match item.parent().and_then(ElementRef::wrap) {
+        Some(parent) => item_notes(parent),
+        None => ItemNotes {
+            portability: None,
+            stability: None,
+            deprecated: false,
+        },
+    };
 
     SubDocument {
         name,
-        portability_note: portability,
-        stability_note: stability,
-        deprecated,
+        portability_note: notes
+            .portability
+            .or_else(|| item.select(&PORTABILITY_SELECTOR).next().map(node_text)),
+        stability_note: notes
+            .stability
+            .or_else(|| item.select(&STABILITY_SELECTOR).next().map(node_text)),
+        deprecated: notes.deprecated || item.select(&DEPRECATED_SELECTOR).next().is_some(),
         summary: None,
     }
 }
 
-fn parse_document_paragraph(paragraph: ElementRef) -> Option<String> {
-    use regex::Regex;
-    lazy_static::lazy_static! {
-        static ref DANGLING_LINK: Regex = Regex::new(r#"<a href="[^h].*">([\s\S]*)</a>"#).unwrap();
-    }
+/// Converts a block of a document into Telegram HTML.
+/// Returns `None` if it is not a paragraph, a code block or a list, or if it is empty.
+pub(super) fn parse_document_paragraph(paragraph: ElementRef) -> Option<String> {
     match paragraph.value().name() {
         "p" => {
-            let inner_html = paragraph.inner_html();
-            let dangling_link_removed = DANGLING_LINK.replace_all(&inner_html, "$1");
-            Some(dangling_link_removed.to_string())
+            let text = inline_html(paragraph);
+            if text.trim().is_empty() {
+                None
+            } else {
+                Some(text.trim().to_string())
+            }
+        }
+        "div" | "pre" => {
+            // e.g. <pre><code class="language-toml">, or rustdoc which only highlights Rust
+            let language = paragraph
+                .descendants()
+                .filter_map(ElementRef::wrap)
+                .find_map(|element| {
+                    element
+                        .value()
+                        .classes()
+                        .find(|class| class.starts_with("language-"))
+                })
+                .unwrap_or("language-rust");
+            Some(format!(
+                "<pre><code class=\"{}\">{}</code></pre>",
+                crate::util::escape_html_entities(language),
+                crate::util::escape_html_entities(node_text(paragraph).trim_end())
+            ))
+        }
+        "ul" | "ol" => {
+            let ordered = paragraph.value().name() == "ol";
+            let items: Vec<String> = paragraph
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|item| item.value().name() == "li")
+                .enumerate()
+                .map(|(i, item)| {
+                    let bullet = if ordered {
+                        format!("{}.", i + 1)
+                    } else {
+                        "•".to_string()
+                    };
+                    format!("{} {}", bullet, inline_html(item).trim())
+                })
+                .collect();
+            if items.is_empty() {
+                None
+            } else {
+                Some(items.join("\n"))
+            }
         }
-        "div" => Some(format!(
-            "<pre><code class=\"language-rust\">{}</code></pre>",
-            crate::util::escape_html_entities(&node_text(paragraph))
-        )),
         _ => None,
     }
 }
 
-// returns the root url of document without a slash
-async fn get_latest_document(crate_name: &str) -> reqwest::Result<Option<String>> {
-    if let Some(std) = get_std_rs(crate_name) {
-        Ok(Some(std))
+/// Keeps only the inline formatting Telegram understands, unwrapping every other element.
+/// Links which are not absolute are unwrapped as well, as they lead nowhere from Telegram.
+fn inline_html(parent: ElementRef) -> String {
+    use crate::util::escape_html_entities;
+    use scraper::Node;
+
+    let mut text = String::new();
+    for child in parent.children() {
+        match child.value() {
+            Node::Text(content) => text.push_str(&escape_html_entities(content)),
+            Node::Element(element) => {
+                let inner = ElementRef::wrap(child).map(inline_html).unwrap_or_default();
+                match element.name() {
+                    "b" | "strong" => text.push_str(&format!("<b>{}</b>", inner)),
+                    "i" | "em" => text.push_str(&format!("<i>{}</i>", inner)),
+                    "s" | "del" | "strike" => text.push_str(&format!("<s>{}</s>", inner)),
+                    "code" => text.push_str(&format!("<code>{}</code>", inner)),
+                    "br" => text.push('\n'),
+                    "img" => {
+                        if let Some(alt) = element.attr("alt") {
+                            text.push_str(&escape_html_entities(alt));
+                        }
+                    }
+                    "a" => match element.attr("href") {
+                        Some(href) if href.starts_with("http") && !inner.trim().is_empty() => text
+                            .push_str(&format!(
+                                "<a href=\"{}\">{}</a>",
+                                href.replace('"', "&quot;"),
+                                inner
+                            )),
+                        _ => text.push_str(&inner),
+                    },
+                    _ => text.push_str(&inner),
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Crates of the standard library, documented in doc.rust-lang.org
+const STD_CRATES: &[&str] = &["alloc", "core", "proc_macro", "std", "test"];
+
+/// Splits the version out of the crate of a path,
+/// e.g. `tokio@0.2.13::runtime` into `tokio::runtime` and `0.2.13`
+fn split_pinned_version(path: &str) -> (String, Option<&str>) {
+    let first = path.split("::").next().unwrap_or_default();
+    let (crate_name, version) = split_version(first);
+    (format!("{}{}", crate_name, &path[first.len()..]), version)
+}
+
+// returns the root url of document with a slash
+// `version` is a version of docs.rs, or a channel or a release of the standard library
+async fn get_document_root(crate_name: &str, version: Option<&str>) -> FetchResult<Option<String>> {
+    if STD_CRATES.contains(&crate_name) {
+        Ok(get_std_rs(crate_name, version))
     } else {
-        get_docs_rs(crate_name).await
+        get_docs_rs(crate_name, version).await
     }
 }
 
-fn get_std_rs(crate_name: &str) -> Option<String> {
-    match crate_name {
-        "alloc" | "core" | "proc_macro" | "std" | "text" => {
-            Some(format!("https://doc.rust-lang.org/stable/{}/", crate_name))
+/// Whether the version the crate of a path is pinned to, if any, can be looked up:
+/// a channel or a release for the standard library, or a version or a requirement for docs.rs.
+pub fn is_valid_pin(path: &str) -> bool {
+    let (crate_name, version) = split_version(path.split("::").next().unwrap_or_default());
+    match version {
+        None => true,
+        Some(channel) if STD_CRATES.contains(&crate_name) => is_std_channel(channel),
+        Some(version) => version == "latest" || semver::VersionReq::parse(version).is_ok(),
+    }
+}
+
+/// e.g. `nightly`, `beta`, `stable` or `1.41.0`
+fn is_std_channel(channel: &str) -> bool {
+    ["stable", "beta", "nightly"].contains(&channel) || semver::Version::parse(channel).is_ok()
+}
+
+fn get_std_rs(crate_name: &str, channel: Option<&str>) -> Option<String> {
+    let channel = channel.unwrap_or("stable");
+    if is_std_channel(channel) {
+        Some(format!(
+            "https://doc.rust-lang.org/{}/{}/",
+            channel, crate_name
+        ))
+    } else {
+        None
+    }
+}
+
+async fn get_docs_rs(crate_name: &str, version: Option<&str>) -> FetchResult<Option<String>> {
+    let url = match version {
+        Some(version) => format!("https://docs.rs/{}/{}", crate_name, version),
+        None => format!("https://docs.rs/{}", crate_name),
+    };
+    if let Some(location) = DOCS_RS_CACHE.get(&url) {
+        return Ok(location);
+    }
+    let response = cache::get(&url).await?;
+    let location = match response.location() {
+        Some(location) if response.status() == StatusCode::FOUND => {
+            let mut location = location.to_owned();
+            if !location.ends_with('/') {
+                location.push('/');
+            }
+            Some(location)
         }
         _ => None,
+    };
+    if !response.status().is_server_error() {
+        DOCS_RS_CACHE.insert(url, location.clone());
     }
+    Ok(location)
 }
 
-async fn get_docs_rs(crate_name: &str) -> reqwest::Result<Option<String>> {
-    let response = WEB_CLIENT
-        .get(&format!("https://docs.rs/{}", crate_name))
-        .send()
-        .await?;
-    if response.status() == StatusCode::FOUND {
-        let location = response
-            .headers()
-            .get(header::LOCATION)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(structure_type: StructureType, name: &str, html: &str) -> CrateDocument {
+        let structure = CrateStructure {
+            module: &["demo"],
+            name,
+            structure_type,
+        };
+        structure
+            .parse_document(&Html::parse_document(html))
+            .expect("the page must be parsed")
+    }
+
+    fn subdocuments<'a>(document: &'a CrateDocument, heading: &str) -> Vec<&'a SubDocument> {
+        match document.sections.iter().find(|(h, _)| h == heading) {
+            Some((_, Article::SubDocuments(subdocuments))) => subdocuments.iter().collect(),
+            _ => panic!("no section `{}`", heading),
+        }
+    }
+
+    const OLD_ENUM: &str = r##"<section id="main">
+<h1 class="fqn"><span class="in-band">Enum <a href="index.html">demo</a>::<a class="enum">Shape</a></span></h1>
+<div class="docblock type-decl"><pre class="rust enum">pub enum Shape { Circle, Square }</pre></div>
+<div class="stability"><div class="stab portability">This is supported on <strong>feature="shapes"</strong> only.</div></div>
+<div class="docblock"><p>A shape.</p><h1 id="examples">Examples</h1><p>Draw one.</p></div>
+<h2 id="variants" class="variants small-section-header">Variants</h2>
+<div id="variant.Circle" class="variant small-section-header"><code>Circle</code></div>
+<div id="variant.Square" class="variant small-section-header"><code>Square</code></div>
+<h2 id="implementations" class="small-section-header">Implementations</h2>
+<h3 id="impl" class="impl"><code class="in-band">impl Shape</code></h3>
+<div class="impl-items"><h4 id="method.area" class="method"><code>pub fn area(&amp;self) -&gt; f64</code></h4>
+<div class="stability"><div class="stab deprecated">Deprecated</div></div>
+<div class="docblock"><p>The area.</p></div></div>
+</section>"##;
+
+    const CURRENT_ENUM: &str = r##"<section id="main-content" class="content">
+<div class="main-heading"><div class="rustdoc-breadcrumbs"><a href="index.html">demo</a></div>
+<h1>Enum <span class="enum">Shape</span><button id="copy-path">Copy item path</button></h1></div>
+<pre class="rust item-decl"><code>pub enum Shape { Circle, Square }</code></pre>
+<span class="item-info"><div class="stab portability">Available on <strong>crate feature <code>shapes</code></strong> only.</div></span>
+<details class="toggle top-doc" open><summary class="hideme"><span>Expand description</span></summary>
+<div class="docblock"><p>A shape.</p><h2 id="examples"><a class="doc-anchor" href="#examples">§</a>Examples</h2><p>Draw one.</p></div></details>
+<h2 id="variants" class="variants section-header">Variants<a href="#variants" class="anchor">§</a></h2>
+<div class="variants"><section id="variant.Circle" class="variant"><a href="#variant.Circle" class="anchor">§</a><h3 class="code-header">Circle</h3></section>
+<section id="variant.Square" class="variant"><a href="#variant.Square" class="anchor">§</a><h3 class="code-header">Square</h3></section></div>
+<h2 id="implementations" class="section-header">Implementations<a href="#implementations" class="anchor">§</a></h2>
+<div id="implementations-list"><details class="toggle implementors-toggle" open><summary><section id="impl-Shape" class="impl"><h3 class="code-header">impl Shape</h3></section></summary>
+<div class="impl-items"><details class="toggle method-toggle" open><summary><section id="method.area" class="method"><span class="rightside"><a class="src" href="#">Source</a></span><h4 class="code-header">pub fn <a href="#method.area" class="fn">area</a>(&amp;self) -&gt; f64</h4></section>
+<span class="item-info"><div class="stab deprecated"><span>Deprecated</span></div></span></summary>
+<div class="docblock"><p>The area.</p></div></details></div></details></div>
+<h2 id="trait-implementations" class="section-header">Trait Implementations<a href="#trait-implementations" class="anchor">§</a></h2>
+<div id="trait-implementations-list"><details class="toggle implementors-toggle" open><summary><section id="impl-Clone-for-Shape" class="impl"><h3 class="code-header">impl Clone for Shape</h3></section></summary></details></div>
+</section>"##;
+
+    fn assert_enum(document: &CrateDocument) {
+        assert_eq!(document.title, "Enum demo::Shape");
+        assert!(document
+            .definition
+            .as_deref()
             .unwrap()
-            .to_str()
-            .unwrap();
-        let mut location = location.to_owned();
-        if location.chars().rev().next() != Some('/') {
-            location.push('/');
+            .contains("pub enum Shape"));
+        assert!(document
+            .portability_note
+            .as_deref()
+            .unwrap()
+            .contains("shapes"));
+        assert_eq!(document.description, "A shape.");
+        assert_eq!(document.sections[0].0, "Examples");
+        let variants: Vec<&str> = subdocuments(document, "Variants")
+            .iter()
+            .map(|variant| variant.name.as_str())
+            .collect();
+        assert_eq!(
+            variants,
+            vec![
+                "<pre><code class=\"language-rust\">Circle</code></pre>",
+                "<pre><code class=\"language-rust\">Square</code></pre>"
+            ]
+        );
+        let methods = subdocuments(document, "Methods");
+        assert_eq!(methods.len(), 1);
+        assert!(methods[0].name.contains("pub fn area(&amp;self) -&gt; f64"));
+        assert!(methods[0].deprecated);
+    }
+
+    #[test]
+    fn parse_old_enum_page() {
+        assert_enum(&parse(StructureType::Enum, "Shape", OLD_ENUM));
+    }
+
+    #[test]
+    fn parse_current_enum_page() {
+        let document = parse(StructureType::Enum, "Shape", CURRENT_ENUM);
+        assert_enum(&document);
+        let impls = subdocuments(&document, "Trait Implementations");
+        assert!(impls[0].name.contains("impl Clone for Shape"));
+    }
+
+    #[test]
+    fn parse_methods_in_both_layouts() {
+        for html in &[OLD_ENUM, CURRENT_ENUM] {
+            let document = parse(StructureType::Method("enum"), "area", html);
+            assert!(document.definition.unwrap().contains("pub fn area"));
+            assert!(document.deprecated);
+            assert_eq!(document.description, "The area.");
         }
-        Ok(Some(location))
-    } else {
-        Ok(None)
+    }
+
+    #[test]
+    fn parse_module_pages() {
+        let old = r##"<section id="main"><h1 class="fqn"><span class="in-band">Crate <a class="mod">demo</a></span></h1>
+<h2 id="structs" class="section-header">Structs</h2>
+<table><tr class="module-item"><td><a class="struct" href="struct.Point.html">Point</a></td>
+<td class="docblock-short"><p>A point.</p></td></tr></table></section>"##;
+        let listed = r##"<section id="main-content"><div class="main-heading"><h1>Crate <a class="mod">demo</a></h1></div>
+<h2 id="structs" class="section-header">Structs<a href="#structs" class="anchor">§</a></h2>
+<ul class="item-table"><li><div class="item-name"><a class="struct" href="struct.Point.html">Point</a></div>
+<div class="desc docblock-short">A point.</div></li></ul></section>"##;
+        let current = r##"<section id="main-content"><div class="main-heading"><h1>Crate <span>demo</span><button id="copy-path">Copy item path</button></h1></div>
+<h2 id="structs" class="section-header">Structs<a href="#structs" class="anchor">§</a></h2>
+<dl class="item-table"><dt><a class="struct" href="struct.Point.html">Point</a></dt><dd>A point.</dd></dl></section>"##;
+        for html in &[old, listed, current] {
+            let document = parse(StructureType::Module, "demo", html);
+            assert_eq!(document.title, "Crate demo");
+            let structs = subdocuments(&document, "Structs");
+            assert_eq!(structs.len(), 1);
+            assert_eq!(structs[0].name, "Point");
+            assert_eq!(structs[0].summary.as_deref(), Some("A point."));
+        }
+    }
+
+    #[test]
+    fn validate_pinned_versions() {
+        assert!(is_valid_pin("std::vec::Vec"));
+        assert!(is_valid_pin("std@nightly::vec::Vec"));
+        assert!(is_valid_pin("core@1.41.0::mem"));
+        assert!(!is_valid_pin("std@1.x::vec::Vec"));
+        assert!(!is_valid_pin("std@1.99999"));
+        assert!(is_valid_pin("tokio@0.2.13::runtime"));
+        assert!(is_valid_pin("tokio@^0.2"));
+        assert!(is_valid_pin("tokio@latest"));
+        assert!(!is_valid_pin("tokio@nightly::runtime"));
     }
 }
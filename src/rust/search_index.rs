@@ -0,0 +1,560 @@
+use crate::cache::{self, Cache, FetchResult};
+use crate::util::{edit_distance, WEB_CLIENT};
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::Url;
+use scraper::{Html, Selector};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+lazy_static! {
+    /// Parsed search indexes by the URL of the documentation root of a crate.
+    static ref INDEX_CACHE: Cache<String, Arc<SearchIndex>> =
+        Cache::new("search indexes", 20, Duration::from_secs(24 * 60 * 60));
+}
+
+/// Item types in the order of rustdoc's numbering, used when the search script cannot be read.
+const DEFAULT_ITEM_TYPES: &[&str] = &[
+    "mod",
+    "externcrate",
+    "import",
+    "struct",
+    "enum",
+    "fn",
+    "type",
+    "static",
+    "trait",
+    "impl",
+    "tymethod",
+    "method",
+    "structfield",
+    "variant",
+    "macro",
+    "primitive",
+    "associatedtype",
+    "constant",
+    "associatedconstant",
+    "union",
+    "foreigntype",
+    "keyword",
+    "existential",
+    "attr",
+    "derive",
+    "traitalias",
+];
+
+/// Item types in the order exact matches are preferred.
+const TYPE_PRECEDENCE: &[&str] = &[
+    "mod",
+    "struct",
+    "enum",
+    "trait",
+    "union",
+    "type",
+    "traitalias",
+    "fn",
+    "macro",
+    "constant",
+    "static",
+    "derive",
+    "attr",
+    "primitive",
+    "keyword",
+    "method",
+    "tymethod",
+    "variant",
+    "structfield",
+    "associatedconstant",
+    "associatedtype",
+];
+
+/// An item of a crate listed in the search index.
+pub struct Item {
+    /// Item type as named by rustdoc, e.g. `struct`
+    pub kind: String,
+    pub name: String,
+    /// Path of the module containing the item, e.g. `std::vec`
+    pub path: String,
+    /// Type and name of the item containing the item, e.g. `struct` and `Vec` for a method.
+    pub parent: Option<(String, String)>,
+}
+
+impl Item {
+    /// e.g. std::vec::Vec::push
+    pub fn full_path(&self) -> String {
+        match &self.parent {
+            Some((_, parent)) => format!("{}::{}::{}", self.path, parent, self.name),
+            None => format!("{}::{}", self.path, self.name),
+        }
+    }
+}
+
+pub struct SearchIndex {
+    items: Vec<Item>,
+}
+
+impl SearchIndex {
+    /// Finds the item at `path`. When there is no such item, the item with the same name
+    /// whose path shares the most modules with `path` is chosen.
+    /// A path ending with `!` only matches macros.
+    pub fn resolve(&self, path: &str) -> Option<&Item> {
+        let (path, macro_only) = match path.strip_suffix('!') {
+            Some(path) => (path, true),
+            None => (path, false),
+        };
+        let candidates = self
+            .items
+            .iter()
+            .filter(|item| !macro_only || item.kind == "macro");
+        let precedence = |item: &Item| {
+            TYPE_PRECEDENCE
+                .iter()
+                .position(|kind| *kind == item.kind)
+                .unwrap_or(TYPE_PRECEDENCE.len())
+        };
+
+        let exact = candidates
+            .clone()
+            .filter(|item| item.full_path() == path)
+            .min_by_key(|item| precedence(item));
+        if exact.is_some() {
+            return exact;
+        }
+
+        let segments: Vec<String> = path.split("::").map(str::to_lowercase).collect();
+        let (name, modules) = segments.split_last()?;
+        candidates
+            .filter(|item| item.name.to_lowercase() == *name)
+            .max_by_key(|item| {
+                let full_path = item.full_path().to_lowercase();
+                let item_segments: Vec<&str> = full_path.split("::").collect();
+                let shared = modules
+                    .iter()
+                    .filter(|module| item_segments.contains(&module.as_str()))
+                    .count();
+                (
+                    shared,
+                    std::cmp::Reverse(item_segments.len()),
+                    std::cmp::Reverse(precedence(item)),
+                )
+            })
+    }
+
+    /// Suggests the full paths of the items whose names are close to the last segment of `path`
+    /// by edit distance, or contain it. The closest ones come first.
+    pub fn suggest(&self, path: &str, count: usize) -> Vec<String> {
+        let name = path
+            .trim_end_matches('!')
+            .rsplit("::")
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let max_distance = (name.chars().count() / 3).max(1);
+        let mut suggestions: Vec<(usize, String)> = self
+            .items
+            .iter()
+            .filter_map(|item| {
+                let item_name = item.name.to_lowercase();
+                let distance = edit_distance(&name, &item_name);
+                let contains = name.len() >= 3 && item_name.contains(&name);
+                if distance <= max_distance || contains {
+                    Some((distance, item.full_path()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        suggestions.sort_by(|(a_distance, a_path), (b_distance, b_path)| {
+            a_distance
+                .cmp(b_distance)
+                .then_with(|| a_path.len().cmp(&b_path.len()))
+        });
+        let mut seen = HashSet::new();
+        suggestions
+            .into_iter()
+            .map(|(_, path)| path)
+            .filter(|path| seen.insert(path.clone()))
+            .take(count)
+            .collect()
+    }
+}
+
+/// Loads the search index of the documentation of a crate, e.g. `https://docs.rs/serde/1.0.104/serde/`.
+/// Returns `None` if the documentation has no search index.
+pub async fn get_index(crate_location: &str) -> FetchResult<Option<Arc<SearchIndex>>> {
+    if let Some(index) = INDEX_CACHE.get(crate_location) {
+        return Ok(Some(index));
+    }
+
+    let page_url = Url::parse(crate_location)?;
+    let page = cache::get(page_url.as_str()).await?;
+    if !page.status().is_success() {
+        return Ok(None);
+    }
+    let (index_url, script_urls) = script_urls(&page_url, &Html::parse_document(&page.text()));
+    let index_url = match index_url {
+        Some(index_url) => index_url,
+        None => return Ok(None),
+    };
+
+    let mut item_types = None;
+    for url in script_urls {
+        let script = cache::get(url.as_str()).await?;
+        if script.status().is_success() {
+            item_types = parse_item_types(&script.text());
+            if item_types.is_some() {
+                break;
+            }
+        }
+    }
+    let item_types =
+        item_types.unwrap_or_else(|| DEFAULT_ITEM_TYPES.iter().map(|t| t.to_string()).collect());
+
+    // The index is too large to keep in the response cache, only the parsed one is kept
+    let response = WEB_CLIENT.get(index_url.as_str()).send().await?;
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+    let crate_name = crate_location
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    let index = match parse_index(&response.text().await?, crate_name, &item_types) {
+        Some(index) => Arc::new(index),
+        None => return Ok(None),
+    };
+    INDEX_CACHE.insert(crate_location.to_string(), index.clone());
+    Ok(Some(index))
+}
+
+/// Finds the search index, and the scripts which may define the item types, of a documentation page.
+fn script_urls(page_url: &Url, html: &Html) -> (Option<Url>, Vec<Url>) {
+    lazy_static! {
+        static ref VARIABLES_SELECTOR: Selector =
+            Selector::parse("#rustdoc-vars, meta[name=\"rustdoc-vars\"]").unwrap();
+        static ref SCRIPT_SELECTOR: Selector = Selector::parse("script[src]").unwrap();
+    }
+
+    if let Some(variables) = html.select(&VARIABLES_SELECTOR).next() {
+        let variables = variables.value();
+        let index_url = match variables.attr("data-search-index-js") {
+            Some(index) => page_url.join(index).ok(),
+            None => {
+                let root = variables.attr("data-root-path").unwrap_or("../");
+                let suffix = variables.attr("data-resource-suffix").unwrap_or_default();
+                page_url
+                    .join(&format!("{}search-index{}.js", root, suffix))
+                    .ok()
+            }
+        };
+        let search_url = variables.attr("data-search-js").and_then(|search| {
+            let search = match variables.attr("data-static-root-path") {
+                Some(static_root) => format!("{}{}", static_root, search),
+                None => search.to_string(),
+            };
+            page_url.join(&search).ok()
+        });
+        return (index_url, search_url.into_iter().collect());
+    }
+
+    // Older documentation links the scripts directly
+    let sources: Vec<Url> = html
+        .select(&SCRIPT_SELECTOR)
+        .filter_map(|script| script.value().attr("src"))
+        .filter_map(|src| page_url.join(src).ok())
+        .collect();
+    let file_name = |url: &Url| {
+        url.path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let index_url = sources
+        .iter()
+        .find(|url| file_name(url).starts_with("search-index"))
+        .cloned();
+    let script_urls = sources
+        .into_iter()
+        .filter(|url| {
+            let name = file_name(url);
+            !name.starts_with("search-index")
+                && (name.starts_with("search") || name.starts_with("main"))
+        })
+        .collect();
+    (index_url, script_urls)
+}
+
+/// Reads the names of the item types from the search script, in the order of their numbering.
+fn parse_item_types(script: &str) -> Option<Vec<String>> {
+    lazy_static! {
+        static ref ITEM_TYPES: Regex = Regex::new(r"itemTypes\s*=\s*\[([^\]]*)\]").unwrap();
+        static ref ITEM_TYPE: Regex = Regex::new(r#""([a-z]+)""#).unwrap();
+    }
+    let list = ITEM_TYPES.captures(script)?.get(1)?.as_str();
+    let item_types: Vec<String> = ITEM_TYPE
+        .captures_iter(list)
+        .map(|item_type| item_type[1].to_string())
+        .collect();
+    if item_types.is_empty() {
+        None
+    } else {
+        Some(item_types)
+    }
+}
+
+/// Reads the index of a crate from `search-index.js`, either a string given to `JSON.parse`
+/// or, in documentation built before mid-2020, an object literal assigned to `searchIndex`.
+fn crate_data(script: &str, crate_name: &str) -> Option<Value> {
+    if let Some(quoted) = script.split("JSON.parse('").nth(1) {
+        let crates: Value = serde_json::from_str(&unescape_js_string(quoted)?).ok()?;
+        return match crates {
+            Value::Object(mut crates) => crates.remove(crate_name),
+            // e.g. [["std", {...}], ...]
+            Value::Array(crates) => crates
+                .into_iter()
+                .find(|pair| pair.get(0).and_then(Value::as_str) == Some(crate_name))?
+                .get(1)
+                .cloned(),
+            _ => None,
+        };
+    }
+    // e.g. searchIndex["std"] = {...};
+    let assignment = format!("searchIndex[\"{}\"]", crate_name);
+    let literal = script
+        .split(&assignment)
+        .nth(1)?
+        .trim_start()
+        .strip_prefix('=')?;
+    serde_json::Deserializer::from_str(literal)
+        .into_iter::<Value>()
+        .next()?
+        .ok()
+}
+
+/// Parses the items of a crate from `search-index.js`.
+fn parse_index(script: &str, crate_name: &str, item_types: &[String]) -> Option<SearchIndex> {
+    let data = crate_data(script, crate_name)?;
+    let item_type = |value: &Value| {
+        let number = value.as_u64()? as usize;
+        item_types.get(number).cloned()
+    };
+    // Older indexes spell the keys out
+    let parents: Vec<(String, String)> = data
+        .get("p")
+        .or_else(|| data.get("paths"))
+        .and_then(Value::as_array)
+        .map(|parents| {
+            parents
+                .iter()
+                .filter_map(|parent| {
+                    Some((
+                        item_type(parent.get(0)?)?,
+                        parent.get(1)?.as_str()?.to_string(),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut items = Vec::new();
+    match data.get("i").or_else(|| data.get("items")) {
+        // Each item as a row of [type, name, path, description, parent index, ...]
+        Some(Value::Array(rows)) if rows.first().is_some_and(Value::is_array) => {
+            let mut path = String::new();
+            for row in rows {
+                let kind = match row.get(0).and_then(item_type) {
+                    Some(kind) => kind,
+                    None => continue,
+                };
+                if let Some(row_path) = row.get(2).and_then(Value::as_str) {
+                    if !row_path.is_empty() {
+                        path = row_path.to_string();
+                    }
+                }
+                let parent = row
+                    .get(4)
+                    .and_then(Value::as_u64)
+                    .and_then(|i| parents.get(i as usize))
+                    .cloned();
+                items.push(Item {
+                    kind,
+                    name: row.get(1)?.as_str()?.to_string(),
+                    path: path.clone(),
+                    parent,
+                });
+            }
+        }
+        // Each column as an array
+        _ => {
+            let names = data.get("n")?.as_array()?;
+            let kinds: Vec<Option<String>> = match data.get("t")? {
+                Value::Array(kinds) => kinds.iter().map(item_type).collect(),
+                // A letter per item, counted from `A`
+                Value::String(kinds) => kinds
+                    .chars()
+                    .map(|c| {
+                        item_types
+                            .get((c as usize).checked_sub('A' as usize)?)
+                            .cloned()
+                    })
+                    .collect(),
+                _ => return None,
+            };
+            let paths = column_paths(data.get("q"), names.len());
+            let parent_indexes = data.get("i").and_then(Value::as_array);
+            for (i, name) in names.iter().enumerate() {
+                let kind = match kinds.get(i).cloned().flatten() {
+                    Some(kind) => kind,
+                    None => continue,
+                };
+                // Parent indexes start from 1, 0 is for no parent
+                let parent = parent_indexes
+                    .and_then(|indexes| indexes.get(i))
+                    .and_then(Value::as_u64)
+                    .and_then(|index| index.checked_sub(1))
+                    .and_then(|index| parents.get(index as usize))
+                    .cloned();
+                items.push(Item {
+                    kind,
+                    name: name.as_str().unwrap_or_default().to_string(),
+                    path: paths[i].clone(),
+                    parent,
+                });
+            }
+        }
+    }
+    Some(SearchIndex { items })
+}
+
+/// Expands the module paths of the items, which are given only where they change.
+/// They are either an array with an empty string for the same path as the previous item,
+/// or an array of pairs of the item index and the path.
+fn column_paths(paths: Option<&Value>, count: usize) -> Vec<String> {
+    let mut expanded = vec![String::new(); count];
+    let paths = match paths.and_then(Value::as_array) {
+        Some(paths) => paths,
+        None => return expanded,
+    };
+    let mut changes: Vec<(usize, &str)> = if paths.iter().any(Value::is_array) {
+        paths
+            .iter()
+            .filter_map(|pair| Some((pair.get(0)?.as_u64()? as usize, pair.get(1)?.as_str()?)))
+            .collect()
+    } else {
+        paths
+            .iter()
+            .enumerate()
+            .filter_map(|(i, path)| Some((i, path.as_str()?)))
+            .filter(|(_, path)| !path.is_empty())
+            .collect()
+    };
+    changes.sort_by_key(|(i, _)| *i);
+    let mut path = "";
+    let mut changes = changes.into_iter().peekable();
+    for (i, expanded) in expanded.iter_mut().enumerate() {
+        while let Some((_, next)) = changes.next_if(|(start, _)| *start <= i) {
+            path = next;
+        }
+        *expanded = path.to_string();
+    }
+    expanded
+}
+
+/// Reads a single-quoted JavaScript string up to its closing quote.
+fn unescape_js_string(quoted: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => return Some(text),
+            '\\' => match chars.next()? {
+                // Line continuation
+                '\n' => {}
+                escaped @ '\\' | escaped @ '\'' => text.push(escaped),
+                escaped => {
+                    text.push('\\');
+                    text.push(escaped);
+                }
+            },
+            c => text.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn default_item_types() -> Vec<String> {
+        DEFAULT_ITEM_TYPES.iter().map(|t| t.to_string()).collect()
+    }
+
+    fn assert_demo_items(index: &SearchIndex) {
+        let paths: Vec<String> = index.items.iter().map(Item::full_path).collect();
+        assert_eq!(paths, vec!["demo::Foo", "demo::Foo::bar"]);
+        let method = index.resolve("demo::Foo::bar").unwrap();
+        assert_eq!(method.kind, "method");
+        assert_eq!(
+            method.parent,
+            Some(("struct".to_string(), "Foo".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_index_of_rows() {
+        let script = r#"var searchIndex = JSON.parse('{\
+"demo":{"doc":"It\'s a demo","i":[[3,"Foo","demo","",null,null],[11,"bar","","",0,null]],"p":[[3,"Foo"]]}\
+}');
+initSearch(searchIndex);"#;
+        let index = parse_index(script, "demo", &default_item_types()).unwrap();
+        assert_demo_items(&index);
+    }
+
+    #[test]
+    fn parse_index_of_columns() {
+        let script = r#"var searchIndex = new Map(JSON.parse('[\
+["demo",{"t":"DL","n":["Foo","bar"],"q":[[0,"demo"]],"i":[0,1],"p":[[3,"Foo"]]}]\
+]'));"#;
+        let index = parse_index(script, "demo", &default_item_types()).unwrap();
+        assert_demo_items(&index);
+    }
+
+    #[test]
+    fn parse_index_of_object_literal() {
+        let script = r#"var searchIndex={};
+searchIndex["other"] = {"doc":"","items":[],"paths":[]};
+searchIndex["demo"] = {"doc":"","items":[[3,"Foo","demo","",null,null],[11,"bar","","",0,null]],"paths":[[3,"Foo"]]};
+initSearch(searchIndex);"#;
+        let index = parse_index(script, "demo", &default_item_types()).unwrap();
+        assert_demo_items(&index);
+        assert!(parse_index(script, "missing", &default_item_types()).is_none());
+    }
+
+    #[test]
+    fn column_paths_of_empty_strings() {
+        let paths = json!(["a", "", "b", ""]);
+        assert_eq!(column_paths(Some(&paths), 4), vec!["a", "a", "b", "b"]);
+    }
+
+    #[test]
+    fn column_paths_of_pairs() {
+        let paths = json!([[0, "a"], [2, "b"]]);
+        assert_eq!(column_paths(Some(&paths), 3), vec!["a", "a", "b"]);
+        assert_eq!(column_paths(None, 2), vec!["", ""]);
+    }
+
+    #[test]
+    fn unescape_js_string_until_closing_quote() {
+        assert_eq!(
+            unescape_js_string(r#"It\'s \\ \"x\"' rest"#).as_deref(),
+            Some(r#"It's \ \"x\""#)
+        );
+        assert_eq!(unescape_js_string("a\\\nb'").as_deref(), Some("ab"));
+        assert_eq!(unescape_js_string("unterminated"), None);
+    }
+}
@@ -0,0 +1,433 @@
+use super::crates::{resolve_features, with_implicit_features};
+use crate::cache::{self, Cache, FetchResult};
+use futures::StreamExt;
+use lazy_static::lazy_static;
+use log::warn;
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Resolution stops after this many crate versions to bound the number of index requests.
+const NODE_LIMIT: usize = 500;
+/// How many crate indexes are fetched at once.
+const CONCURRENT_REQUESTS: usize = 8;
+
+lazy_static! {
+    /// Trees by the exact root version, which may still change as dependencies are released.
+    static ref TREE_CACHE: Cache<(String, String), Arc<DependencyTree>> =
+        Cache::new("trees", 100, Duration::from_secs(60 * 60));
+}
+
+/// A line of the crates.io index, describing a single published version.
+#[derive(Deserialize)]
+struct IndexVersion {
+    name: String,
+    vers: String,
+    deps: Vec<IndexDependency>,
+    features: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    features2: HashMap<String, Vec<String>>,
+    yanked: bool,
+}
+
+#[derive(Deserialize)]
+struct IndexDependency {
+    /// Name of the dependency as used in the features, which may be renamed.
+    name: String,
+    req: String,
+    features: Vec<String>,
+    optional: bool,
+    default_features: bool,
+    kind: Option<String>,
+    /// The platform the dependency is used on, e.g. `cfg(windows)`.
+    target: Option<String>,
+    /// The actual crate name if the dependency is renamed.
+    package: Option<String>,
+}
+
+/// A crate name and its exact version.
+type NodeKey = (String, String);
+
+pub struct DependencyTree {
+    pub root: NodeKey,
+    /// Children of each resolved crate version, with the platform they are used on if specific.
+    nodes: BTreeMap<NodeKey, BTreeMap<NodeKey, Option<String>>>,
+    /// Whether the resolution gave up before reaching every crate.
+    pub truncated: bool,
+    /// Crates whose index could not be fetched, left out of the graph.
+    pub unavailable: BTreeSet<String>,
+}
+
+impl DependencyTree {
+    /// Number of distinct crates in the graph, excluding the root.
+    pub fn unique_count(&self) -> usize {
+        self.nodes
+            .keys()
+            .map(|(name, _)| name)
+            .collect::<BTreeSet<_>>()
+            .len()
+            .saturating_sub(1)
+    }
+
+    /// Crates which appear in the graph with more than one version.
+    pub fn duplicates(&self) -> BTreeMap<&str, Vec<&str>> {
+        let mut versions: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (name, version) in self.nodes.keys() {
+            versions.entry(name).or_default().push(version);
+        }
+        versions.retain(|_, versions| versions.len() > 1);
+        versions
+    }
+
+    /// Renders the graph like `cargo tree`. Subtrees already shown are marked with `(*)`,
+    /// crates with several versions in the graph with `‼`,
+    /// and dependencies used only on some platforms with their target.
+    /// Stops adding lines once the text would exceed `limit` bytes.
+    pub fn render(&self, limit: usize) -> (String, bool) {
+        let duplicates = self.duplicates();
+        let mut text = String::new();
+        let mut visited = BTreeSet::new();
+        let no_children = BTreeMap::new();
+        let mut stack = vec![(&self.root, None, String::new(), String::new())];
+        while let Some((node, target, prefix, child_prefix)) = stack.pop() {
+            let (name, version) = node;
+            let mark = if duplicates.contains_key(name.as_str()) {
+                " ‼"
+            } else {
+                ""
+            };
+            let target_text = match target {
+                Some(target) => format!(" ({})", target),
+                None => String::new(),
+            };
+            let children = self.nodes.get(node).unwrap_or(&no_children);
+            let expanded = visited.insert(node);
+            let repeated = if !expanded && !children.is_empty() {
+                " (*)"
+            } else {
+                ""
+            };
+            let line = format!(
+                "{}{} v{}{}{}{}\n",
+                prefix, name, version, target_text, mark, repeated
+            );
+            if text.len() + line.len() > limit {
+                return (text, true);
+            }
+            text.push_str(&line);
+            if expanded {
+                let count = children.len();
+                for (i, (child, target)) in children.iter().enumerate().rev() {
+                    let last = i + 1 == count;
+                    let (branch, indent) = if last {
+                        ("└── ", "    ")
+                    } else {
+                        ("├── ", "│   ")
+                    };
+                    stack.push((
+                        child,
+                        target.as_deref(),
+                        format!("{}{}", child_prefix, branch),
+                        format!("{}{}", child_prefix, indent),
+                    ));
+                }
+            }
+        }
+        (text, false)
+    }
+}
+
+/// A dependency waiting for its crate index to be resolved.
+struct Pending {
+    parent: Option<NodeKey>,
+    package: String,
+    req: String,
+    target: Option<String>,
+    features: Vec<String>,
+}
+
+/// Resolves the non-dev dependency graph of a crate with its default features enabled.
+/// Each dependency is resolved to its greatest matching version, and the features requested
+/// by every dependent are unified on that version. Dependencies of every platform are resolved.
+/// Crates whose index fails to be fetched are left out rather than failing the whole graph.
+/// `version` may be an exact version or a semver requirement; the latest one is used if absent.
+pub async fn get_tree(
+    crate_name: &str,
+    version: Option<&str>,
+) -> FetchResult<Option<Arc<DependencyTree>>> {
+    let mut index: HashMap<String, Vec<IndexVersion>> = HashMap::new();
+    let root_versions = match get_index(crate_name).await? {
+        Some(versions) => versions,
+        None => return Ok(None),
+    };
+    let root_version = match version {
+        Some(version) if root_versions.iter().any(|v| v.vers == version) => version.to_string(),
+        Some(version) => match VersionReq::parse(version)
+            .ok()
+            .and_then(|req| select_version(&root_versions, &req))
+        {
+            Some(selected) => selected.vers.clone(),
+            None => return Ok(None),
+        },
+        None => match select_version(&root_versions, &VersionReq::STAR) {
+            Some(selected) => selected.vers.clone(),
+            None => return Ok(None),
+        },
+    };
+    let root = (root_versions[0].name.clone(), root_version);
+    if let Some(tree) = TREE_CACHE.get(&root) {
+        return Ok(Some(tree));
+    }
+    index.insert(crate_name.to_string(), root_versions);
+
+    let mut nodes: BTreeMap<NodeKey, BTreeMap<NodeKey, Option<String>>> = BTreeMap::new();
+    let mut node_features: HashMap<NodeKey, BTreeSet<String>> = HashMap::new();
+    let mut truncated = false;
+    let mut unavailable = BTreeSet::new();
+    let mut pending = vec![Pending {
+        parent: None,
+        package: crate_name.to_string(),
+        req: format!("={}", root.1),
+        target: None,
+        features: vec!["default".to_string()],
+    }];
+    while !pending.is_empty() {
+        let missing: BTreeSet<String> = pending
+            .iter()
+            .map(|p| p.package.clone())
+            .filter(|package| !index.contains_key(package))
+            .collect();
+        let fetched: Vec<_> = futures::stream::iter(missing)
+            .map(|package| async move {
+                let versions = get_index(&package).await;
+                (package, versions)
+            })
+            .buffer_unordered(CONCURRENT_REQUESTS)
+            .collect()
+            .await;
+        for (package, versions) in fetched {
+            let versions = match versions {
+                Err(e) => {
+                    warn!("Failed to fetch index of crate `{}`: {}", package, e);
+                    unavailable.insert(package.clone());
+                    Vec::new()
+                }
+                Ok(versions) => versions.unwrap_or_default(),
+            };
+            index.insert(package, versions);
+        }
+
+        let mut next = Vec::new();
+        for Pending {
+            parent,
+            package,
+            req,
+            target,
+            features,
+        } in pending.drain(..)
+        {
+            let versions = match index.get(&package) {
+                Some(versions) => versions,
+                None => continue,
+            };
+            let selected = match VersionReq::parse(&req)
+                .ok()
+                .and_then(|req| select_version(versions, &req))
+            {
+                Some(selected) => selected,
+                None => continue,
+            };
+            let key = (selected.name.clone(), selected.vers.clone());
+            if !nodes.contains_key(&key) {
+                if nodes.len() >= NODE_LIMIT {
+                    truncated = true;
+                    continue;
+                }
+                nodes.insert(key.clone(), BTreeMap::new());
+            }
+            if let Some(parent) = parent {
+                let children = nodes.entry(parent).or_default();
+                // A dependency used on every platform by another path is not platform-specific
+                let edge = children
+                    .entry(key.clone())
+                    .or_insert_with(|| target.clone());
+                if target.is_none() {
+                    *edge = None;
+                }
+            }
+            let first_visit = !node_features.contains_key(&key);
+            let enabled = node_features.entry(key.clone()).or_default();
+            let before = enabled.len();
+            enabled.extend(features);
+            // Nothing new to resolve if the features have not grown since the last visit
+            if !first_visit && enabled.len() == before {
+                continue;
+            }
+            next.extend(dependencies_of(&key, selected, enabled));
+        }
+        pending = next;
+    }
+
+    let tree = Arc::new(DependencyTree {
+        root: root.clone(),
+        nodes,
+        truncated,
+        unavailable,
+    });
+    TREE_CACHE.insert(root, tree.clone());
+    Ok(Some(tree))
+}
+
+/// Lists the non-dev dependencies turned on by `enabled` features of a crate version.
+fn dependencies_of(
+    key: &NodeKey,
+    version: &IndexVersion,
+    enabled: &BTreeSet<String>,
+) -> Vec<Pending> {
+    let mut declared = version.features.clone();
+    declared.extend(version.features2.clone());
+    let optional_dependencies: BTreeSet<&str> = version
+        .deps
+        .iter()
+        .filter(|d| d.optional)
+        .map(|d| d.name.as_str())
+        .collect();
+    let features = with_implicit_features(&declared, optional_dependencies.iter().cloned());
+    let enabled: Vec<String> = enabled
+        .iter()
+        .filter(|feature| features.contains_key(*feature))
+        .cloned()
+        .collect();
+    let closure = resolve_features(&features, &optional_dependencies, &enabled);
+    version
+        .deps
+        .iter()
+        .filter(|d| d.kind.as_deref() != Some("dev"))
+        .filter(|d| !d.optional || closure.dependencies.contains(&d.name))
+        .map(|d| {
+            let prefix = format!("{}/", d.name);
+            let mut features: Vec<String> = d.features.clone();
+            features.extend(
+                closure
+                    .dependency_features
+                    .iter()
+                    .filter_map(|f| f.strip_prefix(&prefix))
+                    .map(str::to_string),
+            );
+            if d.default_features {
+                features.push("default".to_string());
+            }
+            Pending {
+                parent: Some(key.clone()),
+                package: d.package.clone().unwrap_or_else(|| d.name.clone()),
+                req: d.req.clone(),
+                target: d.target.clone(),
+                features,
+            }
+        })
+        .collect()
+}
+
+/// Picks the greatest version satisfying `req`, preferring versions which are not yanked.
+fn select_version<'a>(versions: &'a [IndexVersion], req: &VersionReq) -> Option<&'a IndexVersion> {
+    let matching = |allow_yanked: bool| {
+        versions
+            .iter()
+            .filter(|v| allow_yanked || !v.yanked)
+            .filter_map(|v| Version::parse(&v.vers).ok().map(|parsed| (parsed, v)))
+            .filter(|(parsed, _)| req.matches(parsed))
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, v)| v)
+    };
+    matching(false).or_else(|| matching(true))
+}
+
+/// Fetches every version of a crate from the sparse index of crates.io.
+async fn get_index(crate_name: &str) -> FetchResult<Option<Vec<IndexVersion>>> {
+    let name = crate_name.to_lowercase();
+    // Crate names are ASCII, which also keeps the slicing below on character boundaries
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Ok(None);
+    }
+    let path = match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    };
+    let response = cache::get(&format!("https://index.crates.io/{}", path)).await?;
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+    let versions = response
+        .text()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    Ok(Some(versions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str, version: &str) -> NodeKey {
+        (name.to_string(), version.to_string())
+    }
+
+    fn tree(edges: &[(NodeKey, NodeKey, Option<&str>)]) -> DependencyTree {
+        let mut nodes: BTreeMap<NodeKey, BTreeMap<NodeKey, Option<String>>> = BTreeMap::new();
+        for (parent, child, target) in edges {
+            nodes.entry(child.clone()).or_default();
+            nodes
+                .entry(parent.clone())
+                .or_default()
+                .insert(child.clone(), target.map(str::to_string));
+        }
+        DependencyTree {
+            root: key("app", "1.0.0"),
+            nodes,
+            truncated: false,
+            unavailable: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn render_labels_targets_and_repeats() {
+        let tree = tree(&[
+            (key("app", "1.0.0"), key("log", "0.4.0"), None),
+            (
+                key("app", "1.0.0"),
+                key("winapi", "0.3.9"),
+                Some("cfg(windows)"),
+            ),
+            (key("winapi", "0.3.9"), key("log", "0.4.0"), None),
+            (key("log", "0.4.0"), key("cfg-if", "1.0.0"), None),
+        ]);
+        let (text, cut) = tree.render(usize::MAX);
+        assert!(!cut);
+        let expected = [
+            "app v1.0.0",
+            "├── log v0.4.0",
+            "│   └── cfg-if v1.0.0",
+            "└── winapi v0.3.9 (cfg(windows))",
+            "    └── log v0.4.0 (*)",
+            "",
+        ];
+        assert_eq!(text, expected.join("\n"));
+    }
+
+    #[test]
+    fn render_tolerates_missing_nodes() {
+        let mut tree = tree(&[(key("app", "1.0.0"), key("log", "0.4.0"), None)]);
+        tree.nodes.remove(&key("log", "0.4.0"));
+        let (text, _) = tree.render(usize::MAX);
+        assert_eq!(text, "app v1.0.0\n└── log v0.4.0\n");
+    }
+}
@@ -0,0 +1,299 @@
+use super::books::parse_chapter;
+use super::markdown::join_blocks;
+use super::search::CrateDocument;
+use crate::cache::{self, Cache, FetchResult};
+use crate::util::{escape_html_entities, WEB_CLIENT};
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::Html;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const UNSTABLE_BOOK: &str = "https://doc.rust-lang.org/nightly/unstable-book/";
+/// Declarations of the language features by their status, in the Rust repository.
+const FEATURE_DECLARATIONS: [&str; 3] = [
+    "https://raw.githubusercontent.com/rust-lang/rust/master/compiler/rustc_feature/src/accepted.rs",
+    "https://raw.githubusercontent.com/rust-lang/rust/master/compiler/rustc_feature/src/unstable.rs",
+    "https://raw.githubusercontent.com/rust-lang/rust/master/compiler/rustc_feature/src/removed.rs",
+];
+const CODE_SEARCH: &str = "https://api.github.com/search/code";
+const RUST_SOURCES: &str = "https://raw.githubusercontent.com/rust-lang/rust/master/";
+
+lazy_static! {
+    static ref STATUS_CACHE: Cache<(), Arc<HashMap<String, FeatureStatus>>> =
+        Cache::new("feature_statuses", 1, Duration::from_secs(24 * 60 * 60));
+    static ref LIBRARY_STATUS_CACHE: Cache<String, Option<FeatureStatus>> = Cache::new(
+        "library_feature_statuses",
+        100,
+        Duration::from_secs(24 * 60 * 60)
+    );
+}
+
+/// What has become of a feature.
+#[derive(Clone)]
+struct FeatureStatus {
+    /// e.g. accepted, unstable, incomplete, internal, removed
+    state: String,
+    /// The release it was stabilized, added or removed in.
+    since: String,
+    issue: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct CodeSearch {
+    items: Vec<CodeSearchItem>,
+}
+
+#[derive(Deserialize)]
+struct CodeSearchItem {
+    /// e.g. library/core/src/option.rs
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct Issue {
+    title: String,
+    /// e.g. open, closed
+    state: String,
+}
+
+/// Finds the feature gate a stability note refers to,
+/// e.g. `never_type` of `Experimental (never_type #35121)`.
+pub fn feature_of(stability_note: &str) -> Option<String> {
+    lazy_static! {
+        static ref FEATURE: Regex = Regex::new(r"\((\w+)(?: #\d+)?\)").unwrap();
+    }
+    FEATURE
+        .captures(stability_note)
+        .map(|captures| captures[1].to_string())
+}
+
+/// Shows a feature gate with its entry in the Unstable Book, its tracking issue
+/// and its status. Returns `None` if neither the Unstable Book, rustc nor the standard library knows it.
+pub async fn get_feature(name: &str) -> FetchResult<Option<CrateDocument>> {
+    let name = name.trim().to_lowercase().replace('-', "_");
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Ok(None);
+    }
+    let statuses = get_statuses().await?;
+    let mut status = statuses.get(&name).cloned();
+
+    let mut entry = None;
+    for kind in &["language-features", "library-features"] {
+        let url = format!("{}{}/{}.html", UNSTABLE_BOOK, kind, name.replace('_', "-"));
+        let response = cache::get(&url).await?;
+        if response.status().is_success() {
+            entry = Some((*kind, url, response.text()));
+            break;
+        }
+    }
+    // Stabilized library features have neither an entry nor a declaration in rustc
+    if entry.is_none() && status.is_none() {
+        status = get_library_status(&name).await;
+        if status.is_none() {
+            return Ok(None);
+        }
+    }
+
+    let mut issue = status.as_ref().and_then(|status| status.issue);
+    let mut description = Vec::new();
+    let mut sections = Vec::new();
+    let mut title = escape_html_entities(&name);
+    let mut kind_text = "Language feature";
+    if let Some((kind, url, html)) = entry {
+        if kind == "library-features" {
+            kind_text = "Library feature";
+        }
+        title = format!(
+            "<a href=\"{}\">{}</a>",
+            url.replace('"', "&quot;"),
+            escape_html_entities(&name)
+        );
+        if let Some(chapter) = parse_chapter(&Html::parse_document(&html)) {
+            for paragraph in &chapter.description {
+                // e.g. The tracking issue for this feature is: #35121
+                if paragraph.contains("tracking issue") {
+                    issue = issue.or_else(|| issue_number(paragraph));
+                } else {
+                    description.push(paragraph.clone());
+                }
+            }
+            sections = chapter.into_sections();
+        }
+    }
+
+    let mut definition = vec![kind_text.to_string()];
+    if let Some(number) = issue {
+        let issue_text = match get_issue(number).await {
+            Some(issue) => format!(
+                "Tracking issue <a href=\"https://github.com/rust-lang/rust/issues/{number}\">#{number}</a> \
+                ({state}) <i>{title}</i>",
+                number = number,
+                state = escape_html_entities(&issue.state),
+                title = escape_html_entities(&issue.title)
+            ),
+            None => format!(
+                "Tracking issue <a href=\"https://github.com/rust-lang/rust/issues/{number}\">#{number}</a>",
+                number = number
+            ),
+        };
+        definition.push(issue_text);
+    }
+    definition.push(match &status {
+        Some(status) if status.state == "accepted" => {
+            format!(
+                "<b>Stabilized</b> in {}",
+                escape_html_entities(&status.since)
+            )
+        }
+        Some(status) if status.state == "removed" => {
+            format!("<b>Removed</b> in {}", escape_html_entities(&status.since))
+        }
+        Some(status) => format!(
+            "<b>Unstable</b> ({}) since {}",
+            escape_html_entities(&status.state),
+            escape_html_entities(&status.since)
+        ),
+        None => "<b>Unstable</b>".to_string(),
+    });
+
+    Ok(Some(CrateDocument {
+        title: format!("Feature {}", title),
+        definition: Some(definition.join("\n")),
+        portability_note: None,
+        stability_note: None,
+        deprecated: false,
+        description: join_blocks(&description),
+        sections,
+    }))
+}
+
+fn issue_number(text: &str) -> Option<u32> {
+    lazy_static! {
+        static ref ISSUE: Regex = Regex::new(r"#(\d+)").unwrap();
+    }
+    ISSUE
+        .captures(text)
+        .and_then(|captures| captures[1].parse().ok())
+}
+
+/// Fetches the title and the state of an issue of the Rust repository.
+/// Returns `None` on failure, as the issue is only an addition to the feature.
+async fn get_issue(number: u32) -> Option<Issue> {
+    let url = format!(
+        "https://api.github.com/repos/rust-lang/rust/issues/{}",
+        number
+    );
+    let response = match cache::get(&url).await {
+        Err(e) => {
+            log::warn!("Failed to fetch issue #{}: {}", number, e);
+            return None;
+        }
+        Ok(response) => response,
+    };
+    if !response.status().is_success() {
+        return None;
+    }
+    response.json().ok()
+}
+
+/// Finds the release a library feature was stabilized in by its `#[stable]` attributes
+/// in the standard library, e.g. `#[stable(feature = "rust1", since = "1.0.0")]`.
+/// GitHub searches code only for authenticated users, so this needs a token in `GITHUB_TOKEN`.
+/// Returns `None` without the token or on failure, as the status is only an addition to the feature.
+async fn get_library_status(name: &str) -> Option<FeatureStatus> {
+    lazy_static! {
+        static ref STABLE: Regex =
+            Regex::new(r#"#\[stable\(\s*feature\s*=\s*"(\w+)",\s*since\s*=\s*"([^"]+)""#).unwrap();
+    }
+
+    let token = std::env::var("GITHUB_TOKEN").ok()?;
+    if let Some(status) = LIBRARY_STATUS_CACHE.get(name) {
+        return status;
+    }
+    let query = format!("\"{}\" stable repo:rust-lang/rust path:library", name);
+    let search = match search_code(&query, &token).await {
+        Err(e) => {
+            log::warn!("Failed to search library feature `{}`: {}", name, e);
+            return None;
+        }
+        Ok(search) => search,
+    };
+
+    let mut status = None;
+    // The attribute is usually in the first files found
+    for item in search.items.iter().take(3) {
+        let response = match cache::get(&format!("{}{}", RUST_SOURCES, item.path)).await {
+            Err(e) => {
+                log::warn!("Failed to fetch `{}`: {}", item.path, e);
+                continue;
+            }
+            Ok(response) => response,
+        };
+        if !response.status().is_success() {
+            continue;
+        }
+        let text = response.text();
+        if let Some(captures) = STABLE
+            .captures_iter(&text)
+            .find(|captures| captures[1] == *name)
+        {
+            status = Some(FeatureStatus {
+                state: "accepted".to_string(),
+                since: captures[2].to_string(),
+                issue: None,
+            });
+            break;
+        }
+    }
+    LIBRARY_STATUS_CACHE.insert(name.to_string(), status.clone());
+    status
+}
+
+async fn search_code(query: &str, token: &str) -> FetchResult<CodeSearch> {
+    let response = WEB_CLIENT
+        .get(CODE_SEARCH)
+        .query(&[("q", query)])
+        .bearer_auth(token)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.json().await?)
+}
+
+/// Reads the status of every language feature from its declaration in rustc,
+/// e.g. `(accepted, associated_consts, "1.20.0", Some(29646)),`
+async fn get_statuses() -> FetchResult<Arc<HashMap<String, FeatureStatus>>> {
+    lazy_static! {
+        static ref DECLARATION: Regex =
+            Regex::new(r#"\(\s*(\w+),\s*(\w+),\s*"([^"]+)",\s*(?:Some\((\d+)\)|None)"#).unwrap();
+    }
+
+    if let Some(statuses) = STATUS_CACHE.get(&()) {
+        return Ok(statuses);
+    }
+    let mut statuses = HashMap::new();
+    for url in FEATURE_DECLARATIONS.iter() {
+        let response = cache::get(url).await?;
+        if !response.status().is_success() {
+            continue;
+        }
+        for captures in DECLARATION.captures_iter(&response.text()) {
+            statuses.insert(
+                captures[2].to_string(),
+                FeatureStatus {
+                    state: captures[1].to_string(),
+                    since: captures[3].to_string(),
+                    issue: captures
+                        .get(4)
+                        .and_then(|issue| issue.as_str().parse().ok()),
+                },
+            );
+        }
+    }
+    let statuses = Arc::new(statuses);
+    STATUS_CACHE.insert((), statuses.clone());
+    Ok(statuses)
+}
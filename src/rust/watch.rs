@@ -0,0 +1,193 @@
+use super::crates::CrateVersion;
+use crate::storage::Tree;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::Path;
+use tokio::sync::Mutex;
+
+/// Watched crates by the chat id and the crate name, listing the crates of a chat.
+const SUBSCRIPTIONS: Tree<(i64, String), Watch> = Tree::new("subscriptions");
+/// The same subscriptions by the crate name and the chat id, listing the chats of a crate.
+const WATCHERS: Tree<(String, i64), Watch> = Tree::new("watchers");
+/// Versions of each watched crate already notified.
+const SEEN_VERSIONS: Tree<String, SeenVersions> = Tree::new("seen_versions");
+
+lazy_static! {
+    /// Serializes the updates of the trees, which are read and written in several steps.
+    static ref LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Watch {
+    /// Whether to notify yanked versions as well.
+    pub yanked: bool,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct SeenVersions {
+    published: BTreeSet<String>,
+    yanked: BTreeSet<String>,
+}
+
+impl SeenVersions {
+    fn from_versions(versions: &[CrateVersion]) -> Self {
+        SeenVersions {
+            published: versions.iter().map(|v| v.version.clone()).collect(),
+            yanked: versions
+                .iter()
+                .filter(|v| v.yanked)
+                .map(|v| v.version.clone())
+                .collect(),
+        }
+    }
+}
+
+/// The file the subscriptions were saved to before they moved into the storage.
+#[derive(Deserialize)]
+struct LegacySubscriptions {
+    chats: BTreeMap<i64, BTreeMap<String, Watch>>,
+    seen: BTreeMap<String, SeenVersions>,
+}
+
+/// A release or a yank to tell a chat about.
+pub struct Notification {
+    pub chat_id: i64,
+    pub version: String,
+    pub yanked: bool,
+}
+
+/// Moves the subscriptions saved at `path` by an older version into the storage,
+/// keeping those already in the storage. The file is renamed afterwards not to import it again.
+/// Returns the number of imported subscriptions, or `None` if there is no file to import.
+pub async fn import(path: &Path) -> io::Result<Option<usize>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let legacy: LegacySubscriptions = serde_json::from_slice(&std::fs::read(path)?)?;
+    let _lock = LOCK.lock().await;
+    let mut count = 0;
+    for (chat_id, watches) in legacy.chats {
+        for (crate_name, watch) in watches {
+            if WATCHERS.get(&(crate_name.clone(), chat_id))?.is_none() {
+                WATCHERS.insert(&(crate_name.clone(), chat_id), &watch)?;
+                SUBSCRIPTIONS.insert(&(chat_id, crate_name), &watch)?;
+                count += 1;
+            }
+        }
+    }
+    for (crate_name, seen) in legacy.seen {
+        if SEEN_VERSIONS.get(&crate_name)?.is_none() {
+            SEEN_VERSIONS.insert(&crate_name, &seen)?;
+        }
+    }
+    std::fs::rename(path, path.with_extension("json.imported"))?;
+    Ok(Some(count))
+}
+
+/// Makes a chat watch a crate, or updates the options if it is already watched.
+/// The current `versions` of the crate are considered already notified.
+pub async fn subscribe(
+    chat_id: i64,
+    crate_name: &str,
+    watch: Watch,
+    versions: &[CrateVersion],
+) -> io::Result<()> {
+    let _lock = LOCK.lock().await;
+    let crate_name = crate_name.to_string();
+    if SEEN_VERSIONS.get(&crate_name)?.is_none() {
+        SEEN_VERSIONS.insert(&crate_name, &SeenVersions::from_versions(versions))?;
+    }
+    WATCHERS.insert(&(crate_name.clone(), chat_id), &watch)?;
+    SUBSCRIPTIONS.insert(&(chat_id, crate_name), &watch)
+}
+
+/// Stops a chat watching a crate, whose name is compared as crates.io does,
+/// ignoring the case and the difference between `-` and `_`.
+/// Returns the name of the crate if it has been watched.
+pub async fn unsubscribe(chat_id: i64, crate_name: &str) -> io::Result<Option<String>> {
+    let normalize = |name: &str| name.to_lowercase().replace('-', "_");
+    let _lock = LOCK.lock().await;
+    let key = SUBSCRIPTIONS
+        .entries_starting_with(&chat_id)?
+        .into_iter()
+        .map(|(key, _)| key)
+        .find(|(_, name)| normalize(name) == normalize(crate_name));
+    let (chat_id, crate_name) = match key {
+        Some(key) => key,
+        None => return Ok(None),
+    };
+    SUBSCRIPTIONS.remove(&(chat_id, crate_name.clone()))?;
+    WATCHERS.remove(&(crate_name.clone(), chat_id))?;
+    // Forget the crate once no chat watches it
+    if WATCHERS.entries_starting_with(&crate_name)?.is_empty() {
+        SEEN_VERSIONS.remove(&crate_name)?;
+    }
+    Ok(Some(crate_name))
+}
+
+/// Lists the crates a chat watches in name order.
+pub fn get_subscriptions(chat_id: i64) -> io::Result<Vec<(String, Watch)>> {
+    Ok(SUBSCRIPTIONS
+        .entries_starting_with(&chat_id)?
+        .into_iter()
+        .map(|((_, name), watch)| (name, watch))
+        .collect())
+}
+
+/// Lists every crate watched by any chat.
+pub fn get_watched_crates() -> io::Result<Vec<String>> {
+    Ok(SEEN_VERSIONS
+        .entries()?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect())
+}
+
+/// Compares the current `versions` of a crate with those already notified,
+/// and returns what each watching chat has to be told. Each change is returned only once.
+pub async fn update(crate_name: &str, versions: &[CrateVersion]) -> io::Result<Vec<Notification>> {
+    let _lock = LOCK.lock().await;
+    let crate_name = crate_name.to_string();
+    let mut seen = match SEEN_VERSIONS.get(&crate_name)? {
+        Some(seen) => seen,
+        None => return Ok(Vec::new()),
+    };
+    let mut changes = Vec::new();
+    // Versions are listed newest first, notify in the order of release
+    for version in versions.iter().rev() {
+        if seen.published.insert(version.version.clone()) {
+            changes.push((version.version.clone(), false));
+        }
+        if version.yanked && seen.yanked.insert(version.version.clone()) {
+            changes.push((version.version.clone(), true));
+        }
+    }
+    // A version may be unyanked
+    let yanked: BTreeSet<String> = versions
+        .iter()
+        .filter(|v| v.yanked)
+        .map(|v| v.version.clone())
+        .collect();
+    if changes.is_empty() && yanked == seen.yanked {
+        return Ok(Vec::new());
+    }
+    seen.yanked = yanked;
+    SEEN_VERSIONS.insert(&crate_name, &seen)?;
+
+    let mut notifications = Vec::new();
+    for ((_, chat_id), watch) in WATCHERS.entries_starting_with(&crate_name)? {
+        for (version, yanked) in &changes {
+            if *yanked && !watch.yanked {
+                continue;
+            }
+            notifications.push(Notification {
+                chat_id,
+                version: version.clone(),
+                yanked: *yanked,
+            });
+        }
+    }
+    Ok(notifications)
+}
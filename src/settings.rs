@@ -0,0 +1,23 @@
+use crate::storage::Tree;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// Settings by the chat id.
+const SETTINGS: Tree<i64, Settings> = Tree::new("settings");
+
+/// Preferences of a chat, kept across restarts.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Whether release notifications are sent without a sound.
+    pub silent: bool,
+}
+
+/// Gets the settings of a chat, which are the defaults if the chat has never changed them.
+pub fn get(chat_id: i64) -> io::Result<Settings> {
+    Ok(SETTINGS.get(&chat_id)?.unwrap_or_default())
+}
+
+pub fn set(chat_id: i64, settings: &Settings) -> io::Result<()> {
+    SETTINGS.insert(&chat_id, settings)
+}
@@ -0,0 +1,467 @@
+use lazy_static::lazy_static;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+lazy_static! {
+    static ref STORAGE: RwLock<Arc<dyn Storage>> = RwLock::new(Arc::new(MemoryStorage::default()));
+}
+
+/// A key-value store split into named trees.
+pub trait Storage: Send + Sync {
+    fn get(&self, tree: &str, key: &[u8]) -> io::Result<Option<Vec<u8>>>;
+    fn insert(&self, tree: &str, key: &[u8], value: &[u8]) -> io::Result<()>;
+    fn remove(&self, tree: &str, key: &[u8]) -> io::Result<()>;
+    /// Lists every entry of a tree in key order.
+    fn entries(&self, tree: &str) -> io::Result<Vec<(Vec<u8>, Vec<u8>)>>;
+    /// Lists the entries of a tree whose key starts with `prefix`, in key order.
+    fn scan_prefix(&self, tree: &str, prefix: &[u8]) -> io::Result<Vec<(Vec<u8>, Vec<u8>)>>;
+    /// Gets the entry with the smallest key.
+    fn first(&self, tree: &str) -> io::Result<Option<(Vec<u8>, Vec<u8>)>>;
+    fn len(&self, tree: &str) -> io::Result<usize>;
+    /// Replaces the value of `key` with `new` only if it is still `old`, `None` meaning no entry.
+    /// Returns whether the value has been replaced.
+    fn compare_and_swap(
+        &self,
+        tree: &str,
+        key: &[u8],
+        old: Option<&[u8]>,
+        new: Option<&[u8]>,
+    ) -> io::Result<bool>;
+}
+
+/// Replaces the storage used by every tree. Entries in the previous storage are not carried over.
+pub fn set_storage(storage: Arc<dyn Storage>) {
+    *STORAGE.write().unwrap() = storage;
+}
+
+fn storage() -> Arc<dyn Storage> {
+    STORAGE.read().unwrap().clone()
+}
+
+type MemoryTree = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Keeps everything in memory, losing it on restart.
+#[derive(Default)]
+pub struct MemoryStorage {
+    trees: RwLock<HashMap<String, MemoryTree>>,
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, tree: &str, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        let trees = self.trees.read().unwrap();
+        Ok(trees.get(tree).and_then(|tree| tree.get(key)).cloned())
+    }
+
+    fn insert(&self, tree: &str, key: &[u8], value: &[u8]) -> io::Result<()> {
+        let mut trees = self.trees.write().unwrap();
+        trees
+            .entry(tree.to_string())
+            .or_default()
+            .insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn remove(&self, tree: &str, key: &[u8]) -> io::Result<()> {
+        let mut trees = self.trees.write().unwrap();
+        if let Some(tree) = trees.get_mut(tree) {
+            tree.remove(key);
+        }
+        Ok(())
+    }
+
+    fn entries(&self, tree: &str) -> io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let trees = self.trees.read().unwrap();
+        Ok(trees
+            .get(tree)
+            .map(|tree| {
+                tree.iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn scan_prefix(&self, tree: &str, prefix: &[u8]) -> io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let trees = self.trees.read().unwrap();
+        Ok(trees
+            .get(tree)
+            .map(|tree| {
+                tree.range(prefix.to_vec()..)
+                    .take_while(|(key, _)| key.starts_with(prefix))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn first(&self, tree: &str) -> io::Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let trees = self.trees.read().unwrap();
+        Ok(trees
+            .get(tree)
+            .and_then(|tree| tree.iter().next())
+            .map(|(key, value)| (key.clone(), value.clone())))
+    }
+
+    fn len(&self, tree: &str) -> io::Result<usize> {
+        let trees = self.trees.read().unwrap();
+        Ok(trees.get(tree).map_or(0, BTreeMap::len))
+    }
+
+    fn compare_and_swap(
+        &self,
+        tree: &str,
+        key: &[u8],
+        old: Option<&[u8]>,
+        new: Option<&[u8]>,
+    ) -> io::Result<bool> {
+        let mut trees = self.trees.write().unwrap();
+        let tree = trees.entry(tree.to_string()).or_default();
+        if tree.get(key).map(Vec::as_slice) != old {
+            return Ok(false);
+        }
+        match new {
+            Some(new) => tree.insert(key.to_vec(), new.to_vec()),
+            None => tree.remove(key),
+        };
+        Ok(true)
+    }
+}
+
+/// Keeps everything in an embedded database on disk.
+pub struct SledStorage {
+    database: sled::Db,
+}
+
+impl SledStorage {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(SledStorage {
+            database: sled::open(path)?,
+        })
+    }
+}
+
+impl Storage for SledStorage {
+    fn get(&self, tree: &str, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        let value = self.database.open_tree(tree)?.get(key)?;
+        Ok(value.map(|value| value.to_vec()))
+    }
+
+    fn insert(&self, tree: &str, key: &[u8], value: &[u8]) -> io::Result<()> {
+        self.database.open_tree(tree)?.insert(key, value)?;
+        Ok(())
+    }
+
+    fn remove(&self, tree: &str, key: &[u8]) -> io::Result<()> {
+        self.database.open_tree(tree)?.remove(key)?;
+        Ok(())
+    }
+
+    fn entries(&self, tree: &str) -> io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.database
+            .open_tree(tree)?
+            .iter()
+            .map(|entry| {
+                let (key, value) = entry?;
+                Ok((key.to_vec(), value.to_vec()))
+            })
+            .collect()
+    }
+
+    fn scan_prefix(&self, tree: &str, prefix: &[u8]) -> io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.database
+            .open_tree(tree)?
+            .scan_prefix(prefix)
+            .map(|entry| {
+                let (key, value) = entry?;
+                Ok((key.to_vec(), value.to_vec()))
+            })
+            .collect()
+    }
+
+    fn first(&self, tree: &str) -> io::Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let entry = self.database.open_tree(tree)?.first()?;
+        Ok(entry.map(|(key, value)| (key.to_vec(), value.to_vec())))
+    }
+
+    fn len(&self, tree: &str) -> io::Result<usize> {
+        Ok(self.database.open_tree(tree)?.len())
+    }
+
+    fn compare_and_swap(
+        &self,
+        tree: &str,
+        key: &[u8],
+        old: Option<&[u8]>,
+        new: Option<&[u8]>,
+    ) -> io::Result<bool> {
+        let result = self
+            .database
+            .open_tree(tree)?
+            .compare_and_swap(key, old, new)?;
+        Ok(result.is_ok())
+    }
+}
+
+/// A tree of the storage whose keys and values are stored as JSON.
+pub struct Tree<K, V> {
+    name: &'static str,
+    marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> Tree<K, V> {
+    pub const fn new(name: &'static str) -> Self {
+        Tree {
+            name,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    pub fn get(&self, key: &K) -> io::Result<Option<V>> {
+        match storage().get(self.name, &serde_json::to_vec(key)?)? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn insert(&self, key: &K, value: &V) -> io::Result<()> {
+        storage().insert(
+            self.name,
+            &serde_json::to_vec(key)?,
+            &serde_json::to_vec(value)?,
+        )
+    }
+
+    pub fn remove(&self, key: &K) -> io::Result<()> {
+        storage().remove(self.name, &serde_json::to_vec(key)?)
+    }
+
+    /// Lists every entry of the tree. Entries which fail to deserialize are skipped.
+    pub fn entries(&self) -> io::Result<Vec<(K, V)>> {
+        Ok(deserialize_entries(storage().entries(self.name)?))
+    }
+}
+
+impl<A, B, V> Tree<(A, B), V>
+where
+    A: Serialize + DeserializeOwned,
+    B: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    /// Lists the entries whose key starts with `first`, without reading the rest of the tree.
+    /// Entries which fail to deserialize are skipped.
+    pub fn entries_starting_with(&self, first: &A) -> io::Result<Vec<((A, B), V)>> {
+        // A pair is stored as `[first,second]`, so `[first,` is shared by the entries of `first`
+        let mut prefix = serde_json::to_vec(&[first])?;
+        prefix.pop();
+        prefix.push(b',');
+        Ok(deserialize_entries(
+            storage().scan_prefix(self.name, &prefix)?,
+        ))
+    }
+}
+
+fn deserialize_entries<K, V>(entries: Vec<(Vec<u8>, Vec<u8>)>) -> Vec<(K, V)>
+where
+    K: DeserializeOwned,
+    V: DeserializeOwned,
+{
+    entries
+        .into_iter()
+        .filter_map(|(key, value)| {
+            Some((
+                serde_json::from_slice(&key).ok()?,
+                serde_json::from_slice(&value).ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// A tree whose entries expire when they are not used for long,
+/// or when they are the least recently used beyond the capacity.
+/// Expired entries are removed by `evict`, which is to be called periodically.
+pub struct ExpiringTree<K, V> {
+    name: &'static str,
+    marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> ExpiringTree<K, V> {
+    pub const fn new(name: &'static str) -> Self {
+        ExpiringTree {
+            name,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V> ExpiringTree<K, V>
+where
+    K: Serialize,
+    V: Serialize + DeserializeOwned,
+{
+    /// Gets an entry, marking it as used now.
+    pub fn get(&self, key: &K) -> io::Result<Option<V>> {
+        let key = serde_json::to_vec(key)?;
+        let storage = storage();
+        loop {
+            let entry = match storage.get(self.name, &key)? {
+                Some(entry) => entry,
+                None => return Ok(None),
+            };
+            let (used_at, value): (i64, V) = serde_json::from_slice(&entry)?;
+            let now = now();
+            let refreshed = serde_json::to_vec(&(now, &value))?;
+            // Retry if the entry has been replaced or removed meanwhile, not to bring it back
+            if storage.compare_and_swap(self.name, &key, Some(&entry), Some(&refreshed))? {
+                self.reindex(&*storage, &key, Some(used_at), now)?;
+                return Ok(Some(value));
+            }
+        }
+    }
+
+    pub fn insert(&self, key: &K, value: &V) -> io::Result<()> {
+        let key = serde_json::to_vec(key)?;
+        let storage = storage();
+        let now = now();
+        let previous = storage.get(self.name, &key)?;
+        storage.insert(self.name, &key, &serde_json::to_vec(&(now, value))?)?;
+        let used_at = previous.and_then(|entry| used_at(&entry));
+        self.reindex(&*storage, &key, used_at, now)
+    }
+
+    /// Moves an entry in the usage index from `previous` to `now`.
+    /// The index is updated after the entry, so an index entry is either current or stale,
+    /// and stale ones are dropped by `evict`.
+    fn reindex(
+        &self,
+        storage: &dyn Storage,
+        key: &[u8],
+        previous: Option<i64>,
+        now: i64,
+    ) -> io::Result<()> {
+        let index = self.index_name();
+        if let Some(previous) = previous {
+            if previous != now {
+                storage.remove(&index, &index_key(previous, key))?;
+            }
+        }
+        storage.insert(&index, &index_key(now, key), &[])
+    }
+
+    /// Removes the entries unused for longer than `max_age` seconds,
+    /// then the least recently used ones beyond `capacity`. Returns the number of removed entries.
+    /// Only the entries to remove are read, oldest first, through the usage index.
+    pub fn evict(&self, capacity: usize, max_age: u64) -> io::Result<usize> {
+        let storage = storage();
+        let index = self.index_name();
+        let now = now();
+        let mut count = storage.len(self.name)?;
+        let mut removed = 0;
+        while let Some((index_key, _)) = storage.first(&index)? {
+            if index_key.len() < 8 {
+                storage.remove(&index, &index_key)?;
+                continue;
+            }
+            let (time, key) = index_key.split_at(8);
+            let mut time_bytes = [0; 8];
+            time_bytes.copy_from_slice(time);
+            let indexed_at = u64::from_be_bytes(time_bytes) as i64;
+            if count <= capacity && now - indexed_at <= max_age as i64 {
+                break;
+            }
+            match storage.get(self.name, key)? {
+                // Entries which cannot be read are evicted like the oldest
+                Some(entry) if used_at(&entry).is_none_or(|used_at| used_at == indexed_at) => {
+                    if storage.compare_and_swap(self.name, key, Some(&entry), None)? {
+                        count -= 1;
+                        removed += 1;
+                    } else {
+                        // Used meanwhile, which has indexed it again
+                        continue;
+                    }
+                }
+                // Used again since, or already removed
+                _ => {}
+            }
+            storage.remove(&index, &index_key)?;
+        }
+        Ok(removed)
+    }
+
+    fn index_name(&self) -> String {
+        format!("{}.uses", self.name)
+    }
+}
+
+/// Reads when an entry of an `ExpiringTree` has been used.
+fn used_at(entry: &[u8]) -> Option<i64> {
+    use serde::de::IgnoredAny;
+    serde_json::from_slice::<(i64, IgnoredAny)>(entry)
+        .ok()
+        .map(|(used_at, _)| used_at)
+}
+
+/// Orders the usage index by time, then by key.
+fn index_key(used_at: i64, key: &[u8]) -> Vec<u8> {
+    let mut index_key = (used_at.max(0) as u64).to_be_bytes().to_vec();
+    index_key.extend_from_slice(key);
+    index_key
+}
+
+/// Seconds since the Unix epoch.
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_starting_with_matches_the_whole_first_element() {
+        const TREE: Tree<(i64, String), bool> = Tree::new("test_pairs");
+        for (chat, name) in &[(12, "a"), (123, "b"), (12, "c"), (-12, "d"), (1, "e")] {
+            TREE.insert(&(*chat, name.to_string()), &true).unwrap();
+        }
+        let names = |chat| -> Vec<String> {
+            TREE.entries_starting_with(&chat)
+                .unwrap()
+                .into_iter()
+                .map(|((_, name), _)| name)
+                .collect()
+        };
+        assert_eq!(names(12), vec!["a", "c"]);
+        assert_eq!(names(-12), vec!["d"]);
+        assert!(names(2).is_empty());
+    }
+
+    #[test]
+    fn evict_least_recently_used_beyond_capacity() {
+        const TREE: ExpiringTree<u32, String> = ExpiringTree::new("test_expiring");
+        for key in 0..4 {
+            TREE.insert(&key, &key.to_string()).unwrap();
+        }
+        // Entries used in the same second are ordered by key, so 1 and 2 are the oldest now
+        assert_eq!(TREE.get(&0).unwrap().as_deref(), Some("0"));
+        assert_eq!(TREE.evict(4, 3600).unwrap(), 0);
+        assert_eq!(TREE.evict(2, 3600).unwrap(), 2);
+        assert_eq!(storage().len("test_expiring").unwrap(), 2);
+        // The index does not keep the evicted entries nor the previous use of 0
+        assert_eq!(storage().len("test_expiring.uses").unwrap(), 2);
+        assert!(TREE.get(&1).unwrap().is_none());
+        assert!(TREE.get(&3).unwrap().is_some());
+    }
+}
@@ -1,6 +1,6 @@
+use crate::storage::ExpiringTree;
 use lazy_static::*;
-use std::collections::HashMap;
-use tokio::sync::RwLock;
+use serde::{Deserialize, Serialize};
 
 lazy_static! {
     pub static ref WEB_CLIENT: reqwest::Client = reqwest::Client::builder()
@@ -8,15 +8,74 @@ lazy_static! {
         .redirect(reqwest::redirect::Policy::none())
         .build()
         .expect("Failed to create https client");
-    pub static ref CALLBACK_SESSIONS: RwLock<HashMap<(i64, i32), CallbackSession>> =
-        RwLock::new(HashMap::new());
 }
 
-#[derive(Clone)]
+/// What the buttons of each message are about, by the chat id and the message id.
+pub const CALLBACK_SESSIONS: ExpiringTree<(i64, i32), CallbackSession> =
+    ExpiringTree::new("callback_sessions");
+
+/// Remembers what the buttons of a message are about.
+/// Failures are only logged, as the message has already been sent.
+pub fn save_callback_session(key: (i64, i32), session: &CallbackSession) {
+    if let Err(e) = CALLBACK_SESSIONS.insert(&key, session) {
+        log::error!(
+            "Failed to save callback session of message {key:?}: {error}",
+            key = key,
+            error = e
+        );
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 pub enum CallbackSession {
-    Docs,
+    /// A document whose sections are opened by the buttons, and where it comes from.
+    Docs(DocumentSource),
+    /// Names of the crates listed in a message, each of which opens its card.
+    CrateList(Vec<String>),
+    /// The crate and its version whose card is shown.
+    Crate { name: String, version: String },
+    /// Name of the crate whose versions are listed.
+    Versions(String),
+    /// Name of the crate whose dependents are listed.
+    ReverseDependencies(String),
+    /// The crate and its version whose features are listed.
+    Features { name: String, version: String },
+    /// Ids of the advisories listed in a message, each of which opens the advisory.
+    Advisories(Vec<String>),
+    /// Paths suggested for a document not found, each of which opens the document.
+    DocsSuggestions(Vec<String>),
+    /// Names of the lints suggested for a lint not found, each of which opens the lint.
+    LintSuggestions(Vec<String>),
+    /// Addresses of the chapters found in a book, each of which opens the chapter.
+    BookChapters(Vec<String>),
 }
 
+/// What a document shown in a message has been fetched from,
+/// to fetch it again once the saved document has expired.
+#[derive(Clone, Deserialize, Serialize)]
+pub enum DocumentSource {
+    /// The path given to `/docs`, e.g. `tokio::runtime`.
+    Docs(String),
+    /// The readme of a version of a crate.
+    Readme { name: String, version: String },
+    /// The error code given to `/explain`, e.g. `E0382`.
+    Explain(String),
+    /// The lint given to `/lint`, e.g. `clippy::needless_return`.
+    Lint(String),
+    /// The URL of a chapter of a book, e.g. `https://doc.rust-lang.org/book/ch04-01-what-is-ownership.html`.
+    BookChapter(String),
+    /// The feature given to `/feature`, e.g. `never_type`.
+    Feature(String),
+    /// The release given to `/rust`, e.g. `1.41.0`.
+    ReleaseNotes(String),
+}
+
+/// Texts are cut to this many bytes, leaving room for the rest of a message,
+/// as Telegram rejects messages longer than 4096 characters.
+pub const TEXT_LIMIT: usize = 3000;
+/// Telegram rejects keyboards with more than 100 buttons.
+pub const BUTTON_LIMIT: usize = 99;
+
 pub fn escape_html_entities(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -34,3 +93,26 @@ pub fn size_humanize(number: usize) -> String {
         number.to_string()
     }
 }
+
+/// Levenshtein distance between two strings, counted in characters.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + if a == *b { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Splits `name@version` into the name and the optional version part.
+pub fn split_version(spec: &str) -> (&str, Option<&str>) {
+    match spec.find('@') {
+        Some(index) => (&spec[..index], Some(&spec[index + 1..])),
+        None => (spec, None),
+    }
+}